@@ -4,19 +4,92 @@ use std::{env, fs, io::Write};
 
 fn main() {
     let args: Vec<String> = env::args().collect();
-    if args.len() > 2 {
-        eprintln!("Usage: {} [file_path]", args[0]);
-        std::process::exit(1);
+    let mut file_path: Option<&str> = None;
+
+    let mut args_iter = args[1..].iter();
+    while let Some(arg) = args_iter.next() {
+        match arg.as_str() {
+            "--help" => {
+                println!("{}", help_text());
+                return;
+            }
+            "--legacy-precedence" => fncalc::set_legacy_precedence(true),
+            "--physical-constants" => fncalc::set_physical_constants(true),
+            "--complex" => fncalc::set_complex_numbers(true),
+            "--exact" => fncalc::set_number_mode(fncalc::NumberMode::Exact),
+            "--float" => fncalc::set_number_mode(fncalc::NumberMode::Float),
+            "--precision" => match args_iter.next().and_then(|digits| digits.parse().ok()) {
+                Some(digits) if (1..=fncalc::MAX_PRECISION).contains(&digits) => {
+                    fncalc::set_number_mode(fncalc::NumberMode::Precise(digits))
+                }
+                _ => usage(&args[0]),
+            },
+            "--integer" => match args_iter.next().map(|integer_type| integer_type.parse()) {
+                Some(Ok(integer_type)) => {
+                    fncalc::set_number_mode(fncalc::NumberMode::Integer(integer_type))
+                }
+                _ => usage(&args[0]),
+            },
+            "--wrap" => fncalc::set_overflow(fncalc::Overflow::Wrap),
+            "--radix" => match args_iter.next().map(|radix| radix.parse()) {
+                Some(Ok(radix)) => fncalc::set_radix(radix),
+                _ => usage(&args[0]),
+            },
+            "--angle-mode" => match args_iter.next().map(|mode| mode.parse()) {
+                Some(Ok(mode)) => fncalc::set_angle_mode(mode),
+                _ => usage(&args[0]),
+            },
+            _ if file_path.is_none() && !arg.starts_with("--") => file_path = Some(arg),
+            _ => usage(&args[0]),
+        }
+    }
+
+    match file_path {
+        Some(file_path) => process_file(file_path),
+        None => interpreter(),
     }
-    if args.len() == 2 {
-        if args[1] == "--help" {
-            println!("{}", HELP_TEXT);
+}
+
+fn usage(program: &str) -> ! {
+    eprintln!(
+        "Usage: {} [--legacy-precedence] [--physical-constants] [--complex] [--exact] [--float] [--precision digits] [--integer i8|i16|i32|i64|u8|u16|u32|u64] [--wrap] [--radix dec|hex|bin|oct] [--angle-mode deg|rad|grad] [file_path]",
+        program
+    );
+    std::process::exit(1);
+}
+
+fn help_text() -> String {
+    let mut builtins = String::new();
+    for builtin in fncalc::builtin_functions() {
+        builtins.push_str(&format!(
+            "{} ({} argument{}): {}\n",
+            builtin.name(),
+            builtin.arity(),
+            if builtin.arity() == fncalc::Arity::Exact(1) { "" } else { "s" },
+            builtin.doc()
+        ));
+    }
+
+    let mut constants = String::new();
+    let mut physical_constants = String::new();
+    for constant in fncalc::constants() {
+        let line = format!(
+            "{} = {}: {}\n",
+            constant.name(),
+            constant.literal(),
+            constant.doc()
+        );
+        if constant.is_physical() {
+            physical_constants.push_str(&line);
         } else {
-            process_file(&args[1]);
+            constants.push_str(&line);
         }
-    } else {
-        interpreter();
     }
+
+    HELP_TEXT
+        .replace("{builtins}", builtins.trim_end())
+        .replace("{constants}", constants.trim_end())
+        .replace("{physical_constants}", physical_constants.trim_end())
 }
 
 fn process_file(file_path: &str) {
@@ -110,6 +183,163 @@ You can then use the variable in other expressions.
 
 y = x + 1
 
+Several variables can be assigned at once, either from \
+a list of the same length or from several values, which \
+are all evaluated before the first assignment.
+
+a, b = [1, 2]
+x, y = y, x
+
+# Numbers
+
+Numbers can be written with a fractional part, in scientific \
+notation, or in hexadecimal, binary and octal form. Underscores \
+can be used to separate digits.
+
+3.14
+.5
+6.022e23
+1_000_000
+0xFF
+0b1010
+0o17
+
+# Exact fractions
+
+Use 'mode exact' to calculate with exact fractions instead \
+of rounded decimal numbers, and 'mode decimal' to switch \
+back. Starting fnCalc with '--exact' does the same. \
+'+', '-', '*', '/', '%', integer powers, 'min' and 'max' \
+keep results exact, which are printed like '1/3' or \
+'1 2/3'. Other functions and constants like 'pi' turn \
+them into decimal numbers. Integer powers which are too \
+large to be exact, like '(1/7)^100', are out of range.
+
+mode exact
+1/3 * 3
+1/6 + 1/2
+
+# Precise numbers
+
+Numbers have 28 significant digits. Use 'precision 50' \
+to calculate with 50 significant digits instead, or any \
+other number up to 1000, and 'mode decimal' to switch \
+back. Starting fnCalc with '--precision 50' does the same. \
+Results are then printed with all of their digits, and \
+large numbers like '2^200' in scientific notation. \
+Variables keep their precise values after 'mode decimal', \
+which are printed with 6 decimal places like other \
+results from then on.
+
+precision 50
+2^100
+sqrt(2)
+pi
+
+The operators, constants like 'pi' and 'e', and the \
+functions sqrt, exp, ln, log, sin, cos, tan, asin, acos, \
+atan, atan2, abs, floor, ceil, round, trunc, sign, min and \
+max calculate with the full precision. Other functions, \
+like 'cosh', only have the usual precision.
+
+# Floating point numbers
+
+Use 'mode float' to calculate with 64-bit floating point \
+numbers, which is much faster but not exact for decimal \
+fractions, and 'mode decimal' to switch back. Starting \
+fnCalc with '--float' does the same. Instead of errors, \
+results follow IEEE 754: dividing by zero gives 'inf' and \
+results outside a function's domain are 'NaN'.
+
+mode float
+1/0
+sqrt(-1)
+0.1 + 0.2 == 0.3
+
+# Integer numbers
+
+Use 'mode u8', 'mode i32' or another of the integer types \
+i8, i16, i32, i64, u8, u16, u32 and u64 to calculate with \
+integers of a fixed width, and 'mode decimal' to switch \
+back. Starting fnCalc with '--integer u8' does the same. \
+Division and functions like sqrt truncate their results, \
+and results which don't fit into the type are an error. After 'mode wrap' they wrap \
+around instead, like in C, and 'mode checked' switches back. \
+Hex, binary and octal literals are the bits of the type, so \
+0xff is -1 in the i8 mode. \
+'mode hex', 'mode bin' and 'mode oct' print results in \
+another base, with negative numbers in two's complement, \
+and 'mode dec' switches back. Starting fnCalc with '--wrap' \
+and '--radix hex' does the same.
+
+mode u8
+mode hex
+~0x0f
+0xf0 >> 4
+mode wrap
+255 + 1
+
+# Complex numbers
+
+Start fnCalc with '--complex' to use complex numbers. \
+'i' is then the imaginary unit, unless a variable named \
+'i' exists, and functions like sqrt, ln and '^' return \
+complex results where real ones don't exist.
+
+z = 3 + 4i
+sqrt(-4)
+(-8)^(1/3)
+
+Use re, im, conj, arg and abs to take complex numbers \
+apart. Comparisons with '<' and '>', '%' and most other \
+built-in functions only accept real numbers.
+
+# Implicit multiplication
+
+A number, variable or parenthesized expression written \
+directly next to another one, without whitespace in between, \
+is multiplied with it.
+
+2pi
+3x + 4(x - 1)
+(x + 1)(x - 1)
+
+Implicit multiplication binds tighter than '*' and '/', \
+but looser than '^' and unary operators, so '1/2x' means \
+'1/(2x)' and '2x^2' means '2(x^2)'. Something like 'f(x)' \
+stays a function call if 'f' is a defined function.
+
+# Lists
+
+Lists are written like '[1, 2, 3]' and can be stored in \
+variables, passed to functions and returned from them. \
+'xs[0]' is the first element of a list and 'xs[-1]' the \
+last one, 'xs[1:3]' is the list of the elements from index \
+1 up to, but not including, index 3. Like with implicit \
+multiplication, there can't be whitespace before the '['. \
+'len' counts the elements of a list, 'append' returns a new \
+list with more elements at its end, and 'sum', 'prod', \
+'min' and 'max' also accept a list instead of their \
+arguments.
+
+xs = [3, 1, 4, 1, 5]
+xs[0] + xs[-1]
+xs[1:3]
+xs = append(xs, 9)
+sum(xs) / len(xs)
+
+# Strings
+
+Strings are written in double quotes, like \"hello\", and \
+can contain '\\\"', '\\\\', '\\n' and '\\t'. They can be joined \
+with '+', compared with '==', '!=', '<' and '>', and 'len' \
+counts their characters. 'str(x)' turns any value into \
+the string it is printed as.
+
+name = \"world\"
+greeting = \"hello, \" + name
+greeting + \", 1/3 is \" + str(1/3)
+
 # Defining functions
 
 You can define functions using the 'fn' keyword.
@@ -121,15 +351,150 @@ fn power(base, exponent) {
     return base * power(base, exponent - 1)
 }
 
+Function calls can be nested up to 500 deep. A call \
+which is returned directly, like 'return f(n - 1)', takes \
+the place of the function returning it though, so such \
+tail recursion isn't limited by that.
+
+fn sum_to(n, acc = 0) {
+    if n == 0 { return acc }
+    return sum_to(n - 1, acc + n)
+}
+sum_to(100000)
+
+A parameter can have a default value like 'dp = 2', \
+which is used when its argument is left out and may \
+refer to the parameters before it. The parameters \
+with a default value come after the others. Arguments \
+can also be passed by the name of their parameter, \
+like 'round_to(x, dp = 3)', after the positional \
+ones. The last parameter can be variadic, like \
+'xs...', and takes the remaining arguments as a list.
+
+fn round_to(x, dp = 2) {
+    return round(x * 10^dp) / 10^dp
+}
+round_to(pi, dp = 3)
+fn avg(xs...) {
+    return sum(xs) / len(xs)
+}
+avg(1, 2, 3, 4)
+
+Functions with different numbers of parameters can \
+share a name, and a call picks the one which takes \
+its number of arguments. Defining a function again \
+with the same number of parameters replaces it.
+
+fn area(r) {
+    return pi * r^2
+}
+fn area(w, h) {
+    return w * h
+}
+area(1) + area(2, 3)
+
+A function can return several values, like 'return q, r', \
+which are returned as a list.
+
+fn divmod(a, b) {
+    return a // b, a % b
+}
+
+# Functions as values
+
+Functions are values as well, which can be stored in \
+variables and passed to other functions. A lambda like \
+'x -> x^2' or '(x, y) -> x + y' is a function without a \
+name, and the name of a built-in or defined function \
+without parentheses, like 'sin', stands for the function \
+itself. 'map(f, xs)' calls a function with each element \
+of a list, 'filter(f, xs)' keeps the elements for which \
+it is true, 'reduce(f, xs)' combines the elements with a \
+function of two arguments, and 'apply(f, xs)' calls a \
+function with the elements of a list as its arguments.
+
+fn sum_of(f, a, b) {
+    return sum(map(f, a..b + 1))
+}
+sum_of(x -> x^2, 1, 10)
+square = x -> x^2
+map(square, filter(x -> x % 2 == 0, 1..10))
+reduce((a, b) -> a * b, 1..6)
+apply(max, [3, 1, 4])
+
+# Scope
+
+A function call has its own variables, which start out \
+as its arguments, so assignments in a function don't \
+change the variables outside of it. Functions can also \
+be defined inside a function, where they are local to \
+it and can call each other, also the ones defined after \
+them. Lambdas and functions defined inside a function \
+capture the variables of the place they are created in, \
+keeping their values at that moment, so later changes \
+to those variables don't affect them. The \
+functions defined at the top level can be called from \
+everywhere, but they don't see the variables at the top \
+level.
+
+fn adder(n) {
+    return x -> x + n
+}
+add5 = adder(5)
+add5(1)
+
+fn count_paths(n) {
+    fn paths(k) {
+        if k < 2 { return 1 }
+        return paths(k - 1) + paths(k - 2)
+    }
+    return paths(n)
+}
+
 # Built-in Functions:
 
 The following built-in functions are available:
-sin, cos, tan, ln, log, abs
 
-For trigonometric functions prepend 'a' for arcus \
-and append 'd' for degree.
+{builtins}
+
+Trigonometric functions take and return angles in \
+radians. Use 'mode deg' or 'mode grad' to switch to \
+degrees or gradians, and 'mode rad' to switch back. \
+The mode can also be set by starting fnCalc with \
+'--angle-mode deg'. The variants ending in 'd', like \
+'sind', always work in degrees.
+
+mode deg
+sin 30
+atan2(1, 1)
+
+The functions sin, cos, tan, ln, log, abs and their \
+variants can also be called without parentheses, like \
+'sin x'. The argument extends over \
+implicit multiplication, so 'sin 2x' means 'sin(2x)'. \
+Defining a function with the same name as a built-in \
+one replaces it.
+
+# Constants
 
-Use 'pi' for an accurate value of the constant.
+The following constants are available:
+
+{constants}
+
+Start fnCalc with '--physical-constants' to also use \
+the CODATA 2018 values of these physical constants in \
+SI units:
+
+{physical_constants}
+
+Constants can't be assigned to, and parameters, loop \
+variables and functions can't take their names either. \
+Some physical constants, like 'h' or 'k_B', are too small \
+for fnCalc's decimal numbers and are kept as precise \
+numbers instead. These are rounded to 6 decimal places \
+like other results, unless that would print them as 0, in \
+which case they are printed with 7 significant digits, \
+like '6.62607e-34'.
 
 # Branches
 
@@ -150,6 +515,13 @@ else {
     print x + 1
 }
 
+To choose between two values inside an expression, use \
+'if' together with 'then' and 'else'. Only the selected \
+value is evaluated. The 'else' part extends as far to \
+the right as possible, so use parentheses to limit it.
+
+y = if x > 0 then x else -x
+
 # Loops
 
 You can use the 'while' keyword with an expression for \
@@ -166,20 +538,62 @@ while x &lt; 10 {
     }
 }
 
+The 'for' keyword runs a loop once for every element of a \
+list, which is stored in the variable before the 'in'. \
+'a..b' is the list of the integers from a up to, but not \
+including, b. 'break' works the same as in 'while' loops.
+
+total = 0
+for x in 1..11 {
+    total = total + x^2
+}
+
+A list can also be built from another one with a loop in \
+brackets, which only keeps the elements for which the \
+optional 'if' is true. Unlike with a 'for' loop, its variable \
+doesn't exist outside of the brackets.
+
+[x^2 for x in 1..10 if x % 2 == 0]
+
 # Printing
 
 You can use the 'print' keyword to print intermediary \
-values in in loops and function calls.
+values in in loops and function calls. Several values \
+separated by commas are printed on one line, with spaces \
+between them.
 
 x = 0
 while x &lt; 5 {
-    print x * 2
+    print \"x * 2 =\", x * 2
     x = x + 1
 }
 
+Strings with an 'f' before the quotes contain expressions \
+in braces, which are replaced by their values. A format \
+after a colon sets how a value is written, similar to \
+Python: '.2f' rounds to two decimal places, '.3e' uses \
+scientific notation, 'x', 'b' and 'o' write integers in \
+hex, binary and octal, and '#x' adds a '0x' prefix. A \
+width like '8' pads the value with spaces, '<8', '^8' \
+and '>8' align it left, centered or right, '*^8' pads \
+it with '*', and '08' with leading zeros. '+' always \
+writes the sign. '{{' and '}}' stand for braces. \
+'format(x, \".2f\")' formats a single value.
+
+v = 12.3456
+print f\"v = {v:.2f} m/s\"
+print f\"{255:#x} {5:03d} {\"left\":<8}|\"
+
 # Miscellaneous
 
-Supported operators: +, -, /, *, ^, %
+Supported operators: +, -, /, *, ^, %, //, &, |, xor, ~, <<, >>, ..
+'//' divides and rounds the quotient down, the bitwise \
+operators only accept integers.
+'^' is right-associative and binds tighter than unary minus, \
+so '2^3^2' is 512 and '-2^2' is -4. Start fnCalc with \
+'--legacy-precedence' to evaluate these the way earlier \
+versions did.
+Comments start with '#', or are enclosed in '/*' and '*/'
 You can use '\\' at the end of a line for multiline input
 Input 'clear' to clear the screen
 Input 'reset' to delete all functions and variables";
@@ -1,6 +1,5 @@
 use crate::expression::Expression;
 use crate::instruction::{self, Instruction, ReturnValue};
-use crate::ZERO;
 
 #[derive(Debug, Clone)]
 pub struct Branch {
@@ -37,10 +36,10 @@ impl Branch {
             Err(e) => return Err(e),
         };
 
-        if condition_result != ZERO {
+        if !condition_result.is_zero() {
             exec_body(&self.body, output_stream)
         } else if let Some(body_else) = self.body_else.as_ref() {
-            exec_body(&body_else, output_stream)
+            exec_body(body_else, output_stream)
         } else {
             Ok(ReturnValue::None)
         }
@@ -56,6 +55,9 @@ fn exec_body(body: &Vec<Instruction>, output_stream: &mut String) -> instruction
                 ReturnValue::Return(value) => {
                     return Ok(ReturnValue::Return(value));
                 }
+                ReturnValue::TailCall(tail_call) => {
+                    return Ok(ReturnValue::TailCall(tail_call));
+                }
                 ReturnValue::Break => {
                     return Ok(ReturnValue::Break);
                 }
@@ -71,3 +73,47 @@ fn exec_body(body: &Vec<Instruction>, output_stream: &mut String) -> instruction
         None => Ok(ReturnValue::None),
     }
 }
+
+#[derive(Debug, Clone)]
+pub struct Conditional {
+    context: String,
+    condition: Expression,
+    body: Expression,
+    body_else: Expression,
+}
+
+impl std::fmt::Display for Conditional {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.context)
+    }
+}
+
+impl Conditional {
+    pub fn new(
+        context: String,
+        condition: Expression,
+        body: Expression,
+        body_else: Expression,
+    ) -> Conditional {
+        Conditional {
+            context,
+            condition,
+            body,
+            body_else,
+        }
+    }
+
+    pub fn exec(&self, output_stream: &mut String) -> instruction::Result {
+        let condition_result = self
+            .condition
+            .exec(output_stream)?
+            .expect("Expressions should always return a value on success");
+
+        // only the selected arm is evaluated
+        if !condition_result.is_zero() {
+            self.body.exec(output_stream)
+        } else {
+            self.body_else.exec(output_stream)
+        }
+    }
+}
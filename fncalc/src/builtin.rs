@@ -0,0 +1,652 @@
+use crate::format;
+use crate::function::Callable;
+use crate::instruction;
+use crate::integer;
+use crate::list;
+use crate::number::{self, Number};
+use crate::precise;
+use crate::session::{self, AngleMode, NumberMode};
+use crate::value::Value;
+use crate::{ONE, ZERO};
+use bigdecimal::BigDecimal;
+use rust_decimal::prelude::*;
+use std::cmp::Ordering;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Arity {
+    Exact(usize),
+    Range(usize, usize),
+    AtLeast(usize),
+}
+
+impl Arity {
+    pub fn accepts(&self, count: usize) -> bool {
+        match self {
+            Arity::Exact(n) => count == *n,
+            Arity::Range(min, max) => count >= *min && count <= *max,
+            Arity::AtLeast(min) => count >= *min,
+        }
+    }
+}
+
+impl fmt::Display for Arity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Arity::Exact(n) => write!(f, "{n}"),
+            Arity::Range(min, max) => write!(f, "{min}-{max}"),
+            Arity::AtLeast(min) => write!(f, "{min}+"),
+        }
+    }
+}
+
+enum Function {
+    // complex arguments are rejected before the function is called,
+    // the second function is used if any of the arguments is a float
+    Real(
+        fn(&[Decimal], &str, usize) -> Result<Decimal, instruction::Error>,
+        fn(&[f64], &str, usize) -> Result<f64, instruction::Error>,
+    ),
+    Value(fn(&[Value], &str, usize) -> Result<Value, instruction::Error>),
+    // the only functions which accept lists, strings and functions as arguments
+    Any(fn(&[Value], &str, usize) -> Result<Value, instruction::Error>),
+    // functions which call the functions passed to them, which may print
+    Higher(fn(&[Value], &str, usize, &mut String) -> Result<Value, instruction::Error>),
+}
+
+// the same closure compiled for both kinds of real numbers
+macro_rules! real {
+    ($function:expr) => {
+        Function::Real($function, $function)
+    };
+}
+
+pub struct Builtin {
+    name: &'static str,
+    arity: Arity,
+    doc: &'static str,
+    function: Function,
+}
+
+impl Builtin {
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn arity(&self) -> Arity {
+        self.arity
+    }
+
+    pub fn doc(&self) -> &'static str {
+        self.doc
+    }
+
+    // the number of arguments has to be checked by the caller
+    pub(crate) fn call(
+        &self,
+        arguments: &[Value],
+        context: &str,
+        pos: usize,
+        output_stream: &mut String,
+    ) -> instruction::Result {
+        if !matches!(self.function, Function::Any(_) | Function::Higher(_))
+            && arguments.iter().any(|arg| {
+                matches!(arg, Value::List(_) | Value::String(_) | Value::Function(_))
+            })
+        {
+            return Err(instruction::Error::new(
+                context.to_string(),
+                pos,
+                instruction::ErrorKind::TypeError,
+            ));
+        }
+
+        let value = match self.function {
+            Function::Real(_, function)
+                if arguments.iter().any(|arg| matches!(arg, Value::Float(_))) =>
+            {
+                number::call(function, arguments, context, pos)?
+            }
+            Function::Real(function, _) => number::call(function, arguments, context, pos)?,
+            Function::Value(function) | Function::Any(function) => {
+                function(arguments, context, pos)?
+            }
+            Function::Higher(function) => function(arguments, context, pos, output_stream)?,
+        };
+
+        // in the integer mode, results like those of 'abs' or 'sqrt' are truncated to
+        // integers like a cast in C
+        let value = match (value, session::settings().number_mode) {
+            (Value::Real(x), NumberMode::Integer(_)) => {
+                match x.trunc().to_i128().and_then(integer::fit) {
+                    Some(x) => Value::Integer(x),
+                    None => {
+                        return Err(instruction::Error::new(
+                            context.to_string(),
+                            pos,
+                            instruction::ErrorKind::OutOfRange,
+                        ))
+                    }
+                }
+            }
+            (value, _) => value,
+        };
+        Ok(instruction::ReturnValue::Value(value))
+    }
+}
+
+impl fmt::Debug for Builtin {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+pub fn get(name: &str) -> Option<&'static Builtin> {
+    BUILTINS.iter().find(|builtin| builtin.name == name)
+}
+
+pub fn all() -> &'static [Builtin] {
+    BUILTINS
+}
+
+// angles are passed to and returned from the trigonometric functions in the session's angle mode
+fn to_radians(angle: &Value, context: &str, pos: usize) -> Result<Value, instruction::Error> {
+    let mode = session::settings().angle_mode;
+    match (angle, mode.half_turn()) {
+        (_, None) => Ok(angle.clone()),
+        (Value::Precise(x), Some(half_turn)) => Ok(Value::Precise(precise::to_radians(
+            x,
+            half_turn,
+            precise::precision(),
+        ))),
+        _ => angle.mul(&Value::Real(mode.to_radians(Decimal::ONE)), context, pos),
+    }
+}
+
+fn from_radians(angle: Value) -> Value {
+    let mode = session::settings().angle_mode;
+    match (angle, mode.half_turn()) {
+        (angle, None) => angle,
+        (Value::Precise(x), Some(half_turn)) => {
+            Value::Precise(precise::from_radians(&x, half_turn, precise::precision()))
+        }
+        (Value::Float(x), Some(half_turn)) => {
+            Value::Float(x * f64::from(half_turn) / std::f64::consts::PI)
+        }
+        (angle, _) => Value::Real(mode.from_radians(angle.re())),
+    }
+}
+
+// rounding functions keep precise numbers precise and floats floats
+fn rounded(
+    argument: &Value,
+    precise: fn(&BigDecimal) -> BigDecimal,
+    float: fn(f64) -> f64,
+    decimal: fn(&Decimal) -> Decimal,
+    context: &str,
+    pos: usize,
+) -> Result<Value, instruction::Error> {
+    match argument {
+        Value::Precise(x) => Ok(Value::Precise(precise(x))),
+        Value::Float(x) => Ok(Value::Float(float(*x))),
+        _ => Ok(Value::Real(decimal(&argument.real(context, pos)?))),
+    }
+}
+
+// a single list argument stands for its elements
+fn elements(args: &[Value]) -> &[Value] {
+    match args {
+        [Value::List(elements)] => elements,
+        _ => args,
+    }
+}
+
+// the smallest argument, or the largest with a reversed ordering
+fn extreme(
+    args: &[Value],
+    ordering: Ordering,
+    context: &str,
+    pos: usize,
+) -> Result<Value, instruction::Error> {
+    let args = elements(args);
+    if args.is_empty() {
+        return Err(instruction::Error::new(
+            context.to_string(),
+            pos,
+            instruction::ErrorKind::DomainError,
+        ));
+    }
+
+    let mut result = &args[0];
+    for argument in args {
+        if argument.compare(result, context, pos)? == ordering {
+            result = argument;
+        }
+    }
+    // the argument itself, so that fractions and integers stay exact
+    Ok(result.clone())
+}
+
+// the function passed to a higher order function
+fn callable<'a>(
+    value: &'a Value,
+    context: &str,
+    pos: usize,
+) -> Result<&'a Callable, instruction::Error> {
+    match value {
+        Value::Function(function) => Ok(function),
+        _ => Err(instruction::Error::new(
+            context.to_string(),
+            pos,
+            instruction::ErrorKind::TypeError,
+        )),
+    }
+}
+
+// the result of a function called by a higher order function, which has to return one
+fn call_value(
+    function: &Callable,
+    arguments: &[Value],
+    context: &str,
+    pos: usize,
+    output_stream: &mut String,
+) -> Result<Value, instruction::Error> {
+    match session::call(function, arguments, &[], context, pos, output_stream)? {
+        instruction::ReturnValue::Value(value) => Ok(value),
+        _ => Err(instruction::Error::new(
+            context.to_string(),
+            pos,
+            instruction::ErrorKind::MissingReturnValue,
+        )),
+    }
+}
+
+static BUILTINS: &[Builtin] = &[
+    Builtin {
+        name: "sin",
+        arity: Arity::Exact(1),
+        doc: "sine of an angle",
+        function: Function::Value(|args, context, pos| to_radians(&args[0], context, pos)?.sin(context, pos)),
+    },
+    Builtin {
+        name: "sind",
+        arity: Arity::Exact(1),
+        doc: "sine of an angle in degrees, regardless of the angle mode",
+        function: real!(|args, context, pos| args[0].to_radians().trig_sin(context, pos)),
+    },
+    Builtin {
+        name: "asin",
+        arity: Arity::Exact(1),
+        doc: "arcus sine",
+        function: Function::Value(|args, context, pos| Ok(from_radians(args[0].asin(context, pos)?))),
+    },
+    Builtin {
+        name: "asind",
+        arity: Arity::Exact(1),
+        doc: "arcus sine in degrees, regardless of the angle mode",
+        function: real!(|args, context, pos| Ok(args[0].trig_asin(context, pos)?.to_degrees())),
+    },
+    Builtin {
+        name: "cos",
+        arity: Arity::Exact(1),
+        doc: "cosine of an angle",
+        function: Function::Value(|args, context, pos| to_radians(&args[0], context, pos)?.cos(context, pos)),
+    },
+    Builtin {
+        name: "cosd",
+        arity: Arity::Exact(1),
+        doc: "cosine of an angle in degrees, regardless of the angle mode",
+        function: real!(|args, context, pos| args[0].to_radians().trig_cos(context, pos)),
+    },
+    Builtin {
+        name: "acos",
+        arity: Arity::Exact(1),
+        doc: "arcus cosine",
+        function: Function::Value(|args, context, pos| Ok(from_radians(args[0].acos(context, pos)?))),
+    },
+    Builtin {
+        name: "acosd",
+        arity: Arity::Exact(1),
+        doc: "arcus cosine in degrees, regardless of the angle mode",
+        function: real!(|args, context, pos| Ok(args[0].trig_acos(context, pos)?.to_degrees())),
+    },
+    Builtin {
+        name: "tan",
+        arity: Arity::Exact(1),
+        doc: "tangent of an angle",
+        function: Function::Value(|args, context, pos| match (args[0].to_real(), session::settings().angle_mode) {
+            (Value::Real(x), AngleMode::Degrees) => Ok(Value::Real(x.trig_tan_deg(context, pos)?)),
+            (Value::Real(x), AngleMode::Gradians) => {
+                Ok(Value::Real((x * Decimal::new(9, 1)).trig_tan_deg(context, pos)?))
+            }
+            (angle, _) => to_radians(&angle, context, pos)?.tan(context, pos),
+        }),
+    },
+    Builtin {
+        name: "tand",
+        arity: Arity::Exact(1),
+        doc: "tangent of an angle in degrees, regardless of the angle mode",
+        function: real!(|args, context, pos| args[0].trig_tan_deg(context, pos)),
+    },
+    Builtin {
+        name: "atan",
+        arity: Arity::Exact(1),
+        doc: "arcus tangent",
+        function: Function::Value(|args, context, pos| Ok(from_radians(args[0].atan(context, pos)?))),
+    },
+    Builtin {
+        name: "atand",
+        arity: Arity::Exact(1),
+        doc: "arcus tangent in degrees, regardless of the angle mode",
+        function: real!(|args, context, pos| Ok(args[0].trig_atan(context, pos)?.to_degrees())),
+    },
+    Builtin {
+        name: "deg",
+        arity: Arity::Exact(1),
+        doc: "converts an angle from radians to degrees",
+        function: real!(|args, _, _| Ok(args[0].to_degrees())),
+    },
+    Builtin {
+        name: "rad",
+        arity: Arity::Exact(1),
+        doc: "converts an angle from degrees to radians",
+        function: real!(|args, _, _| Ok(args[0].to_radians())),
+    },
+    Builtin {
+        name: "ln",
+        arity: Arity::Exact(1),
+        doc: "natural logarithm",
+        function: Function::Value(|args, context, pos| args[0].ln(context, pos)),
+    },
+    Builtin {
+        name: "log",
+        arity: Arity::Range(1, 2),
+        doc: "logarithm to base 10, or to the base given as the second argument",
+        function: Function::Value(|args, context, pos| match args.get(1) {
+            Some(base) => args[0].log_base(base, context, pos),
+            None => args[0].log(context, pos),
+        }),
+    },
+    Builtin {
+        name: "log2",
+        arity: Arity::Exact(1),
+        doc: "base 2 logarithm",
+        function: real!(|args, context, pos| args[0].math_log_base(Number::from_integer(2), context, pos)),
+    },
+    Builtin {
+        name: "abs",
+        arity: Arity::Exact(1),
+        doc: "absolute value, or the magnitude of a complex number",
+        function: Function::Value(|args, context, pos| match args[0] {
+            Value::Rational(_) | Value::Precise(_) if args[0].compare(&ZERO, context, pos)?.is_lt() => {
+                Ok(-args[0].clone())
+            }
+            Value::Rational(_) | Value::Precise(_) => Ok(args[0].clone()),
+            Value::Float(x) => Ok(Value::Float(x.abs())),
+            _ => Ok(Value::Real(args[0].abs(context, pos)?)),
+        }),
+    },
+    Builtin {
+        name: "sqrt",
+        arity: Arity::Exact(1),
+        doc: "square root",
+        function: Function::Value(|args, context, pos| args[0].sqrt(context, pos)),
+    },
+    Builtin {
+        name: "cbrt",
+        arity: Arity::Exact(1),
+        doc: "cube root",
+        function: real!(|args, context, pos| args[0].math_cbrt(context, pos)),
+    },
+    Builtin {
+        name: "exp",
+        arity: Arity::Exact(1),
+        doc: "e raised to the power of the argument",
+        function: Function::Value(|args, context, pos| args[0].exp(context, pos)),
+    },
+    Builtin {
+        name: "floor",
+        arity: Arity::Exact(1),
+        doc: "largest integer less than or equal to the argument",
+        function: Function::Value(|args, context, pos| rounded(&args[0], precise::floor, f64::floor, Decimal::floor, context, pos)),
+    },
+    Builtin {
+        name: "ceil",
+        arity: Arity::Exact(1),
+        doc: "smallest integer greater than or equal to the argument",
+        function: Function::Value(|args, context, pos| rounded(&args[0], precise::ceil, f64::ceil, Decimal::ceil, context, pos)),
+    },
+    Builtin {
+        name: "round",
+        arity: Arity::Range(1, 2),
+        doc: "rounds half away from zero, to the number of decimal places given as the second argument",
+        function: Function::Value(|args, context, pos| {
+            let dp = match args.get(1) {
+                Some(dp) => dp.real(context, pos)?,
+                None => Decimal::ZERO,
+            };
+            match &args[0] {
+                Value::Precise(x) if dp.fract().is_zero() && dp.to_i64().is_some() => {
+                    Ok(Value::Precise(precise::round_dp(x, dp.to_i64().unwrap())))
+                }
+                Value::Float(x) => number::binary(*x, dp.to_f64().unwrap(), Number::math_round, context, pos),
+                x => number::binary(x.real(context, pos)?, dp, Number::math_round, context, pos),
+            }
+        }),
+    },
+    Builtin {
+        name: "trunc",
+        arity: Arity::Exact(1),
+        doc: "integer part of the argument",
+        function: Function::Value(|args, context, pos| rounded(&args[0], precise::trunc, f64::trunc, Decimal::trunc, context, pos)),
+    },
+    Builtin {
+        name: "sign",
+        arity: Arity::Exact(1),
+        doc: "-1, 0 or 1 depending on the sign of the argument",
+        function: Function::Value(|args, context, pos| match args[0].compare(&ZERO, context, pos)? {
+            _ if matches!(args[0], Value::Float(x) if x.is_nan()) => Ok(args[0].clone()),
+            Ordering::Less => Ok(Value::Real(Decimal::NEGATIVE_ONE)),
+            Ordering::Equal => Ok(ZERO),
+            Ordering::Greater => Ok(Value::Real(Decimal::ONE)),
+        }),
+    },
+    Builtin {
+        name: "min",
+        arity: Arity::AtLeast(1),
+        doc: "smallest of the arguments, or of the elements of a list",
+        function: Function::Any(|args, context, pos| extreme(args, Ordering::Less, context, pos)),
+    },
+    Builtin {
+        name: "max",
+        arity: Arity::AtLeast(1),
+        doc: "largest of the arguments, or of the elements of a list",
+        function: Function::Any(|args, context, pos| extreme(args, Ordering::Greater, context, pos)),
+    },
+    Builtin {
+        name: "sum",
+        arity: Arity::AtLeast(1),
+        doc: "sum of the arguments, or of the elements of a list",
+        function: Function::Any(|args, context, pos| {
+            elements(args).iter().try_fold(ZERO, |sum, x| sum.add(x, context, pos))
+        }),
+    },
+    Builtin {
+        name: "prod",
+        arity: Arity::AtLeast(1),
+        doc: "product of the arguments, or of the elements of a list",
+        function: Function::Any(|args, context, pos| {
+            elements(args).iter().try_fold(ONE, |product, x| product.mul(x, context, pos))
+        }),
+    },
+    Builtin {
+        name: "hypot",
+        arity: Arity::Exact(2),
+        doc: "length of the hypotenuse of a right triangle with the given sides",
+        function: real!(|args, context, pos| args[0].math_hypot(args[1], context, pos)),
+    },
+    Builtin {
+        name: "atan2",
+        arity: Arity::Exact(2),
+        doc: "angle of the point (x, y), called as atan2(y, x)",
+        function: Function::Value(|args, context, pos| Ok(from_radians(args[0].atan2(&args[1], context, pos)?))),
+    },
+    Builtin {
+        name: "sinh",
+        arity: Arity::Exact(1),
+        doc: "hyperbolic sine",
+        function: real!(|args, context, pos| args[0].hyp_sinh(context, pos)),
+    },
+    Builtin {
+        name: "cosh",
+        arity: Arity::Exact(1),
+        doc: "hyperbolic cosine",
+        function: real!(|args, context, pos| args[0].hyp_cosh(context, pos)),
+    },
+    Builtin {
+        name: "tanh",
+        arity: Arity::Exact(1),
+        doc: "hyperbolic tangent",
+        function: real!(|args, context, pos| args[0].hyp_tanh(context, pos)),
+    },
+    Builtin {
+        name: "asinh",
+        arity: Arity::Exact(1),
+        doc: "inverse hyperbolic sine",
+        function: real!(|args, context, pos| args[0].hyp_asinh(context, pos)),
+    },
+    Builtin {
+        name: "acosh",
+        arity: Arity::Exact(1),
+        doc: "inverse hyperbolic cosine",
+        function: real!(|args, context, pos| args[0].hyp_acosh(context, pos)),
+    },
+    Builtin {
+        name: "atanh",
+        arity: Arity::Exact(1),
+        doc: "inverse hyperbolic tangent",
+        function: real!(|args, context, pos| args[0].hyp_atanh(context, pos)),
+    },
+    Builtin {
+        name: "re",
+        arity: Arity::Exact(1),
+        doc: "real part of a complex number",
+        function: Function::Value(|args, _, _| match &args[0] {
+            Value::Precise(_) | Value::Float(_) => Ok(args[0].clone()),
+            _ => Ok(Value::Real(args[0].re())),
+        }),
+    },
+    Builtin {
+        name: "im",
+        arity: Arity::Exact(1),
+        doc: "imaginary part of a complex number",
+        function: Function::Value(|args, _, _| Ok(Value::Real(args[0].im()))),
+    },
+    Builtin {
+        name: "conj",
+        arity: Arity::Exact(1),
+        doc: "complex conjugate",
+        function: Function::Value(|args, _, _| Ok(args[0].conj())),
+    },
+    Builtin {
+        name: "arg",
+        arity: Arity::Exact(1),
+        doc: "angle between the positive real axis and a complex number",
+        function: Function::Value(|args, context, pos| match &args[0] {
+            Value::Complex(_) => Ok(from_radians(Value::Real(args[0].arg(context, pos)?))),
+            _ => Ok(from_radians(ZERO.atan2(&args[0], context, pos)?)),
+        }),
+    },
+    Builtin {
+        name: "str",
+        arity: Arity::Exact(1),
+        doc: "the argument as a string, written like it is printed",
+        function: Function::Any(|args, _, _| Ok(Value::String(crate::format_value(args[0].clone())))),
+    },
+    Builtin {
+        name: "format",
+        arity: Arity::Exact(2),
+        doc: "the first argument as a string, written like a field of an f-string with the second one as its format",
+        function: Function::Any(|args, context, pos| match &args[1] {
+            Value::String(spec) => match format::Spec::parse(spec) {
+                Some(spec) => Ok(Value::String(spec.apply(&args[0], context, pos)?)),
+                None => Err(instruction::Error::new(context.to_string(), pos, instruction::ErrorKind::InvalidFormat)),
+            },
+            _ => Err(instruction::Error::new(context.to_string(), pos, instruction::ErrorKind::TypeError)),
+        }),
+    },
+    Builtin {
+        name: "len",
+        arity: Arity::Exact(1),
+        doc: "number of elements of a list or characters of a string",
+        function: Function::Any(|args, context, pos| match &args[0] {
+            Value::List(elements) => Ok(Value::Real(Decimal::from(elements.len()))),
+            Value::String(string) => Ok(Value::Real(Decimal::from(string.chars().count()))),
+            _ => Err(instruction::Error::new(context.to_string(), pos, instruction::ErrorKind::TypeError)),
+        }),
+    },
+    Builtin {
+        name: "append",
+        arity: Arity::AtLeast(2),
+        doc: "the list with the other arguments added at its end",
+        function: Function::Any(|args, context, pos| match &args[0] {
+            Value::List(elements) => Ok(Value::List([elements, &args[1..]].concat())),
+            _ => Err(instruction::Error::new(context.to_string(), pos, instruction::ErrorKind::TypeError)),
+        }),
+    },
+    Builtin {
+        name: "map",
+        arity: Arity::Exact(2),
+        doc: "list of the results of a function called with each element of a list",
+        function: Function::Higher(|args, context, pos, output_stream| {
+            let function = callable(&args[0], context, pos)?;
+            let mut result = Vec::new();
+            for element in list::items(args[1].clone(), context, pos)? {
+                result.push(call_value(function, &[element], context, pos, output_stream)?);
+            }
+            Ok(Value::List(result))
+        }),
+    },
+    Builtin {
+        name: "filter",
+        arity: Arity::Exact(2),
+        doc: "list of the elements of a list for which a function returns true",
+        function: Function::Higher(|args, context, pos, output_stream| {
+            let function = callable(&args[0], context, pos)?;
+            let mut result = Vec::new();
+            for element in list::items(args[1].clone(), context, pos)? {
+                if !call_value(function, std::slice::from_ref(&element), context, pos, output_stream)?.is_zero() {
+                    result.push(element);
+                }
+            }
+            Ok(Value::List(result))
+        }),
+    },
+    Builtin {
+        name: "reduce",
+        arity: Arity::Range(2, 3),
+        doc: "combines the elements of a list with a function of two arguments, starting with the optional third argument",
+        function: Function::Higher(|args, context, pos, output_stream| {
+            let function = callable(&args[0], context, pos)?;
+            let mut elements = list::items(args[1].clone(), context, pos)?.into_iter();
+            let mut result = match args.get(2).cloned().or_else(|| elements.next()) {
+                Some(initial) => initial,
+                None => return Err(instruction::Error::new(context.to_string(), pos, instruction::ErrorKind::DomainError)),
+            };
+            for element in elements {
+                result = call_value(function, &[result, element], context, pos, output_stream)?;
+            }
+            Ok(result)
+        }),
+    },
+    Builtin {
+        name: "apply",
+        arity: Arity::Exact(2),
+        doc: "calls a function with the elements of a list as its arguments",
+        function: Function::Higher(|args, context, pos, output_stream| {
+            let function = callable(&args[0], context, pos)?;
+            let arguments = list::items(args[1].clone(), context, pos)?;
+            call_value(function, &arguments, context, pos, output_stream)
+        }),
+    },
+];
@@ -0,0 +1,181 @@
+use crate::precise;
+use crate::session::{self, NumberMode};
+use crate::Value;
+
+pub struct Constant {
+    name: &'static str,
+    value: &'static str,
+    doc: &'static str,
+    physical: bool,
+}
+
+impl Constant {
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    // the exact literal, which may not be representable as a number of the session
+    pub fn literal(&self) -> &'static str {
+        self.value
+    }
+
+    pub fn doc(&self) -> &'static str {
+        self.doc
+    }
+
+    pub fn is_physical(&self) -> bool {
+        self.physical
+    }
+
+    pub(crate) fn value(&self) -> Option<Value> {
+        // the mathematical constants are calculated to the precision of the precise mode,
+        // the physical ones are exact anyway
+        if let NumberMode::Precise(precision) = session::settings().number_mode {
+            return Some(Value::Precise(match self.name {
+                "pi" => precise::pi(precision),
+                "e" => precise::e(precision),
+                "tau" => precise::tau(precision),
+                "phi" => precise::phi(precision),
+                _ => precise::parse(self.value, 10)?,
+            }));
+        }
+
+        if session::settings().number_mode == NumberMode::Float {
+            return self.value.parse().ok().map(Value::Float);
+        }
+
+        // some of the tiny physical constants have more decimal places than a Decimal
+        // can hold, these are kept as precise numbers
+        precise::parse(self.value, 10).map(Value::from_precise)
+    }
+}
+
+// physical constants are only visible if enabled in the session settings
+pub fn get(name: &str) -> Option<&'static Constant> {
+    let physical = session::settings().physical_constants;
+    CONSTANTS
+        .iter()
+        .find(|constant| constant.name == name && (physical || !constant.physical))
+}
+
+pub fn all() -> &'static [Constant] {
+    CONSTANTS
+}
+
+// physical constants are the CODATA 2018 recommended values in SI units
+static CONSTANTS: &[Constant] = &[
+    Constant {
+        name: "pi",
+        value: "3.1415926535897932384626433833",
+        doc: "ratio of a circle's circumference to its diameter",
+        physical: false,
+    },
+    Constant {
+        name: "e",
+        value: "2.7182818284590452353602874714",
+        doc: "Euler's number, the base of the natural logarithm",
+        physical: false,
+    },
+    Constant {
+        name: "tau",
+        value: "6.2831853071795864769252867666",
+        doc: "ratio of a circle's circumference to its radius",
+        physical: false,
+    },
+    Constant {
+        name: "phi",
+        value: "1.6180339887498948482045868344",
+        doc: "golden ratio",
+        physical: false,
+    },
+    Constant {
+        name: "c",
+        value: "299792458",
+        doc: "speed of light in vacuum [m s^-1]",
+        physical: true,
+    },
+    Constant {
+        name: "G",
+        value: "6.67430e-11",
+        doc: "Newtonian constant of gravitation [m^3 kg^-1 s^-2]",
+        physical: true,
+    },
+    Constant {
+        name: "g_n",
+        value: "9.80665",
+        doc: "standard acceleration of gravity [m s^-2]",
+        physical: true,
+    },
+    Constant {
+        name: "h",
+        value: "6.62607015e-34",
+        doc: "Planck constant [J s]",
+        physical: true,
+    },
+    Constant {
+        name: "hbar",
+        value: "1.054571817e-34",
+        doc: "reduced Planck constant [J s]",
+        physical: true,
+    },
+    Constant {
+        name: "k_B",
+        value: "1.380649e-23",
+        doc: "Boltzmann constant [J K^-1]",
+        physical: true,
+    },
+    Constant {
+        name: "N_A",
+        value: "6.02214076e23",
+        doc: "Avogadro constant [mol^-1]",
+        physical: true,
+    },
+    Constant {
+        name: "R",
+        value: "8.314462618",
+        doc: "molar gas constant [J mol^-1 K^-1]",
+        physical: true,
+    },
+    Constant {
+        name: "F",
+        value: "96485.33212",
+        doc: "Faraday constant [C mol^-1]",
+        physical: true,
+    },
+    Constant {
+        name: "e_charge",
+        value: "1.602176634e-19",
+        doc: "elementary charge [C]",
+        physical: true,
+    },
+    Constant {
+        name: "m_e",
+        value: "9.1093837015e-31",
+        doc: "electron mass [kg]",
+        physical: true,
+    },
+    Constant {
+        name: "m_p",
+        value: "1.67262192369e-27",
+        doc: "proton mass [kg]",
+        physical: true,
+    },
+    Constant {
+        name: "epsilon_0",
+        value: "8.8541878128e-12",
+        doc: "vacuum electric permittivity [F m^-1]",
+        physical: true,
+    },
+    Constant {
+        name: "mu_0",
+        value: "1.25663706212e-6",
+        doc: "vacuum magnetic permeability [N A^-2]",
+        physical: true,
+    },
+    Constant {
+        name: "sigma",
+        value: "5.670374419e-8",
+        doc: "Stefan-Boltzmann constant [W m^-2 K^-4]",
+        physical: true,
+    },
+];
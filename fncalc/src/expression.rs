@@ -1,7 +1,12 @@
+use crate::constant;
+use crate::function::{Callable, FunctionCall};
 use crate::instruction::{self, ReturnValue};
-use crate::session;
+use crate::integer;
+use crate::precise;
+use crate::session::{self, NumberMode};
 use crate::token::{Operator, Parenthesis, Token, TokenKind};
-use crate::{Value, NEGATIVE_ONE, ONE, ZERO};
+use crate::value::{Rational, Value};
+use crate::{ONE, ZERO};
 use rust_decimal::prelude::*;
 
 #[derive(Debug, Clone)]
@@ -24,33 +29,166 @@ enum Operand<'a> {
 impl<'a> Operand<'a> {
     fn get_value(&self, context: &'a str) -> Result<Value, instruction::Error> {
         match self {
-            Operand::Value(val) => Ok(*val),
-            Operand::Identifier(id, pos) => match session::get_variable(id) {
+            Operand::Value(val) => Ok(val.clone()),
+            Operand::Identifier(id, pos) => match session::get_variable(id).or_else(|| imaginary_unit(id)) {
                 Some(val) => Ok(val),
-                None => {
-                    return Err(instruction::Error::new(
-                        context.to_string(),
-                        *pos,
-                        instruction::ErrorKind::IdentifierNotFound,
-                    ));
-                }
+                None => match constant::get(id) {
+                    Some(constant) => match constant.value() {
+                        Some(val) => Ok(val),
+                        None => Err(instruction::Error::new(
+                            context.to_string(),
+                            *pos,
+                            instruction::ErrorKind::OutOfRange,
+                        )),
+                    },
+                    // functions can be used as values by their name
+                    None => match session::get_function(id) {
+                        Some(function) => Ok(Value::Function(function)),
+                        None => Err(instruction::Error::new(
+                            context.to_string(),
+                            *pos,
+                            instruction::ErrorKind::IdentifierNotFound,
+                        )),
+                    },
+                },
             },
         }
     }
 }
 
+// 'i' is the imaginary unit if complex numbers are enabled, unless a variable shadows it
+fn imaginary_unit(id: &str) -> Option<Value> {
+    if id == "i" && session::settings().complex_numbers {
+        Some(Value::I)
+    } else {
+        None
+    }
+}
+
+// number literals are exact fractions in the exact number mode, arbitrary precision
+// numbers in the precise mode, f64 numbers in the float mode and integers of the integer
+// type in the integer mode, literals which don't fit into a Decimal are precise ones
+fn literal(value: &Value) -> Option<Value> {
+    match (value, session::settings().number_mode) {
+        (Value::String(_) | Value::Function(_), _) => Some(value.clone()),
+        (Value::Real(x), NumberMode::Integer(_)) if x.fract().is_zero() => {
+            Some(Value::Integer(integer::fit(x.to_i128()?)?))
+        }
+        (Value::Precise(x), NumberMode::Integer(_)) if x.is_integer() => {
+            Some(Value::Integer(integer::fit(x.to_i128()?)?))
+        }
+        (_, NumberMode::Integer(_)) => None,
+        (Value::Real(_) | Value::Precise(_), NumberMode::Float) => float(value),
+        (Value::Real(x), NumberMode::Exact) => Some(Value::Rational(Rational::from_decimal(*x))),
+        (Value::Real(x), NumberMode::Precise(precision)) => Some(Value::Precise(precise::round(
+            precise::from_decimal(*x),
+            precision,
+        ))),
+        (Value::Precise(x), NumberMode::Precise(precision)) => {
+            Some(Value::Precise(precise::round(x.clone(), precision)))
+        }
+        (Value::Precise(x), _) => literal(&Value::Real(precise::to_decimal(x)?)),
+        _ => Some(value.clone()),
+    }
+}
+
+// parsed from the exact decimal digits of the literal, which rounds to the nearest f64,
+// unlike a conversion of the number
+fn float(value: &Value) -> Option<Value> {
+    let digits = match value {
+        Value::Real(x) => x.to_string(),
+        Value::Precise(x) => x.to_string(),
+        _ => return None,
+    };
+    digits.parse().ok().map(Value::Float)
+}
+
+// hex, binary and octal literals are the bits of the integer type in the integer mode
+fn bits(value: &Value) -> Option<Value> {
+    match (value, session::settings().number_mode) {
+        (Value::Real(x), NumberMode::Integer(_)) => {
+            Some(Value::Integer(integer::from_bits(x.to_i128()?)?))
+        }
+        (Value::Precise(x), NumberMode::Integer(_)) => {
+            Some(Value::Integer(integer::from_bits(x.to_i128()?)?))
+        }
+        _ => literal(value),
+    }
+}
+
+// `a(b)` is a multiplication if `a` is a variable rather than a function,
+// unless the variable holds one
+fn multiplier(f: &FunctionCall) -> Option<Value> {
+    if f.arguments().len() != 1
+        || !f.named_arguments().is_empty()
+        || session::has_function(f.name())
+    {
+        return None;
+    }
+    session::get_variable(f.name())
+        .or_else(|| imaginary_unit(f.name()))
+        .or_else(|| constant::get(f.name()).and_then(|c| c.value()))
+        .filter(|lhs| !matches!(lhs, Value::Function(_)))
+}
+
+fn truth(value: bool) -> Value {
+    literal(if value { &ONE } else { &ZERO }).expect("0 and 1 are valid in every number mode")
+}
+
 impl Expression {
+    pub fn context(&self) -> &str {
+        &self.context
+    }
+
+    // the function call and its position, if the expression is nothing but a call
+    pub fn function_call(&self) -> Option<(&FunctionCall, usize)> {
+        match self.tokens.as_slice() {
+            [token] => match token.kind() {
+                TokenKind::FunctionCall(f) if multiplier(f).is_none() => Some((f, token.pos())),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
     pub fn exec(&self, output_stream: &mut String) -> instruction::Result {
         let mut stack: Vec<Operand> = Vec::new();
 
-        for token in &self.tokens {
+        let mut tokens = self.tokens.iter().peekable();
+        while let Some(token) = tokens.next() {
             match token.kind() {
-                TokenKind::Value(val) => stack.push(Operand::Value(val.clone())),
+                TokenKind::Value(val) | TokenKind::Bits(val) => {
+                    let value = match token.kind() {
+                        TokenKind::Bits(_) => bits(val),
+                        _ => literal(val),
+                    };
+                    // the minimum of a signed integer type like -128 only fits as a whole
+                    let value = match (value, tokens.peek().map(|token| token.kind())) {
+                        (None, Some(TokenKind::Operator(Operator::Neg)))
+                            if matches!(val, Value::Real(_) | Value::Precise(_)) =>
+                        {
+                            tokens.next();
+                            literal(&-val.clone())
+                        }
+                        (value, _) => value,
+                    };
+                    match value {
+                        Some(val) => stack.push(Operand::Value(val)),
+                        None => {
+                            return Err(instruction::Error::new(
+                                self.context.clone(),
+                                token.pos(),
+                                instruction::ErrorKind::InvalidNumberLiteral,
+                            ));
+                        }
+                    }
+                }
                 TokenKind::Operator(op) => match op {
                     Operator::Add
                     | Operator::Sub
                     | Operator::Div
                     | Operator::Mult
+                    | Operator::ImplicitMult
                     | Operator::Mod
                     | Operator::Pow
                     | Operator::And
@@ -58,35 +196,58 @@ impl Expression {
                     | Operator::LessThan
                     | Operator::GreaterThan
                     | Operator::Equal
-                    | Operator::NotEqual => {
+                    | Operator::NotEqual
+                    | Operator::BitAnd
+                    | Operator::BitOr
+                    | Operator::BitXor
+                    | Operator::ShiftLeft
+                    | Operator::ShiftRight
+                    | Operator::IntDiv
+                    | Operator::Range => {
                         Expression::binary_operation(&mut stack, *op, &self.context, token.pos())?
                     }
-                    Operator::Neg
-                    | Operator::Not
-                    | Operator::Sin
-                    | Operator::Sind
-                    | Operator::Asin
-                    | Operator::Asind
-                    | Operator::Cos
-                    | Operator::Cosd
-                    | Operator::Acos
-                    | Operator::Acosd
-                    | Operator::Tan
-                    | Operator::Tand
-                    | Operator::Atan
-                    | Operator::Atand
-                    | Operator::Ln
-                    | Operator::Log
-                    | Operator::Abs => {
+                    Operator::Neg | Operator::Not | Operator::BitNot => {
                         Expression::unary_operation(&mut stack, *op, &self.context, token.pos())?
                     }
                     Operator::Assign => {
                         Expression::assignment_operation(&mut stack, &self.context, token.pos())?
                     }
                 }
-                TokenKind::Identifier(id) => stack.push(Operand::Identifier(&id, token.pos())),
+                TokenKind::Identifier(id) => stack.push(Operand::Identifier(id, token.pos())),
+                TokenKind::Conditional(c) => {
+                    let value = c
+                        .exec(output_stream)?
+                        .expect("Expressions should always return a value on success");
+                    stack.push(Operand::Value(value));
+                }
+                // lambdas capture the variables of the namespace they are evaluated in
+                TokenKind::Lambda(function) => {
+                    stack.push(Operand::Value(Value::Function(Callable::capture(function))))
+                }
+                TokenKind::List(list) => stack.push(Operand::Value(list.exec(output_stream)?)),
+                TokenKind::Comprehension(comprehension) => {
+                    let value = comprehension.exec(&self.context, token.pos(), output_stream)?;
+                    stack.push(Operand::Value(value));
+                }
+                TokenKind::FormatString(string) => {
+                    let value = string.exec(&self.context, token.pos(), output_stream)?;
+                    stack.push(Operand::Value(value));
+                }
+                TokenKind::Index(index) => {
+                    let list = stack.pop().unwrap().get_value(&self.context)?;
+                    let value = index.apply(&list, &self.context, token.pos(), output_stream)?;
+                    stack.push(Operand::Value(value));
+                }
                 TokenKind::FunctionCall(f) => {
-                    match session::call_function(f, token.pos(), output_stream) {
+                    if let Some(lhs) = multiplier(f) {
+                        let rhs = f.arguments()[0]
+                            .exec(output_stream)?
+                            .expect("Expressions should always return a value on success");
+                        stack.push(Operand::Value(lhs.mul(&rhs, &self.context, token.pos())?));
+                        continue;
+                    }
+
+                    match session::call_function(f, &self.context, token.pos(), output_stream) {
                         Ok(output) => match output {
                             ReturnValue::Value(value) => stack.push(Operand::Value(value)),
                             ReturnValue::None => {
@@ -127,6 +288,13 @@ impl Expression {
                 pos,
                 instruction::ErrorKind::InvalidAssignment,
             )),
+            Operand::Identifier(id, _) if constant::get(id).is_some() => Err(
+                instruction::Error::new(
+                    context.to_string(),
+                    pos,
+                    instruction::ErrorKind::InvalidAssignment,
+                ),
+            ),
             Operand::Identifier(id, _) => {
                 session::set_variable(id, rhs);
                 stack.push(lhs);
@@ -144,61 +312,26 @@ impl Expression {
         let rhs = stack.pop().unwrap().get_value(context)?;
         let lhs = stack.pop().unwrap().get_value(context)?;
 
-        let res = match operator {
-            Operator::Add => Ok(lhs + rhs),
-            Operator::Sub => Ok(lhs - rhs),
-            Operator::Mult => Ok(lhs * rhs),
-            Operator::Mod => Ok(lhs % rhs),
-            Operator::Pow => Ok(lhs.math_pow(rhs, context, pos)?),
-            Operator::Div => {
-                if rhs != ZERO {
-                    Ok(lhs / rhs)
-                } else {
-                    Err(instruction::ErrorKind::ZeroDivision)
-                }
-            }
-            Operator::And => {
-                if lhs != ZERO && rhs != ZERO {
-                    Ok(ONE)
-                } else {
-                    Ok(ZERO)
-                }
-            }
-            Operator::Or => {
-                if lhs != ZERO || rhs != ZERO {
-                    Ok(ONE)
-                } else {
-                    Ok(ZERO)
-                }
-            }
-            Operator::LessThan => {
-                if lhs < rhs {
-                    Ok(ONE)
-                } else {
-                    Ok(ZERO)
-                }
-            }
-            Operator::GreaterThan => {
-                if lhs > rhs {
-                    Ok(ONE)
-                } else {
-                    Ok(ZERO)
-                }
-            }
-            Operator::Equal => {
-                if lhs == rhs {
-                    Ok(ONE)
-                } else {
-                    Ok(ZERO)
-                }
-            }
-            Operator::NotEqual => {
-                if lhs != rhs {
-                    Ok(ONE)
-                } else {
-                    Ok(ZERO)
-                }
-            }
+        let val = match operator {
+            Operator::Add => lhs.add(&rhs, context, pos)?,
+            Operator::Sub => lhs.sub(&rhs, context, pos)?,
+            Operator::Mult | Operator::ImplicitMult => lhs.mul(&rhs, context, pos)?,
+            Operator::Mod => lhs.rem(&rhs, context, pos)?,
+            Operator::Pow => lhs.pow(&rhs, context, pos)?,
+            Operator::Div => lhs.div(&rhs, context, pos)?,
+            Operator::IntDiv => lhs.floor_div(&rhs, context, pos)?,
+            Operator::Range => lhs.range(&rhs, context, pos)?,
+            Operator::And => truth(!lhs.is_zero() && !rhs.is_zero()),
+            Operator::Or => truth(!lhs.is_zero() || !rhs.is_zero()),
+            Operator::LessThan => truth(lhs.compare(&rhs, context, pos)?.is_lt()),
+            Operator::GreaterThan => truth(lhs.compare(&rhs, context, pos)?.is_gt()),
+            Operator::Equal => truth(lhs == rhs),
+            Operator::NotEqual => truth(lhs != rhs),
+            Operator::BitAnd => lhs.bit_and(&rhs, context, pos)?,
+            Operator::BitOr => lhs.bit_or(&rhs, context, pos)?,
+            Operator::BitXor => lhs.bit_xor(&rhs, context, pos)?,
+            Operator::ShiftLeft => lhs.shift_left(&rhs, context, pos)?,
+            Operator::ShiftRight => lhs.shift_right(&rhs, context, pos)?,
             Operator::Assign => {
                 panic!(
                     "Do not use binary_operation for assignment, use assignment_operation instead"
@@ -207,15 +340,8 @@ impl Expression {
             _ => panic!("Invalid operator for binary operation"),
         };
 
-        match res {
-            Ok(val) => {
-                stack.push(Operand::Value(val));
-                return Ok(());
-            }
-            Err(e) => {
-                return Err(instruction::Error::new(context.to_string(), pos, e));
-            }
-        };
+        stack.push(Operand::Value(val));
+        Ok(())
     }
 
     fn unary_operation(
@@ -226,58 +352,39 @@ impl Expression {
     ) -> Result<(), instruction::Error> {
         let operand = stack.pop().unwrap().get_value(context)?;
 
-        let res = match operator {
-            Operator::Neg => Ok(operand * NEGATIVE_ONE),
-            Operator::Not => {
-                if operand == ZERO {
-                    Ok(ONE)
-                } else {
-                    Ok(ZERO)
-                }
-            }
-            Operator::Sin => Ok(operand.trig_sin(context, pos)?),
-            Operator::Sind => Ok(operand.to_radians().trig_sin(context, pos)?),
-            Operator::Asin => Ok(operand.trig_asin(context, pos)?),
-            Operator::Asind => Ok(operand.trig_asin(context, pos)?.to_degrees()),
-
-            Operator::Cos => Ok(operand.trig_cos(context, pos)?),
-            Operator::Cosd => Ok(operand.to_radians().trig_cos(context, pos)?),
-            Operator::Acos => Ok(operand.trig_acos(context, pos)?),
-            Operator::Acosd => Ok(operand.trig_acos(context, pos)?.to_degrees()),
-
-            Operator::Tan => Ok(operand.trig_tan(context, pos)?),
-            Operator::Tand => Ok(operand.trig_tan_deg(context, pos)?),
-            Operator::Atan => Ok(operand.trig_atan(context, pos)?),
-            Operator::Atand => Ok(operand.trig_atan(context, pos)?.to_degrees()),
-
-            Operator::Ln => Ok(operand.math_ln(context, pos)?),
-            Operator::Log => Ok(operand.math_log(context, pos)?),
-            Operator::Abs => Ok(operand.abs()),
+        let val = match operator {
+            Operator::Neg => operand.negate(context, pos)?,
+            Operator::Not => truth(operand.is_zero()),
+            Operator::BitNot => operand.bit_not(context, pos)?,
             _ => panic!("Invalid operator for unary operation"),
         };
 
-        match res {
-            Ok(val) => {
-                stack.push(Operand::Value(val));
-                return Ok(());
-            }
-            Err(e) => {
-                return Err(instruction::Error::new(context.to_string(), pos, e));
-            }
-        };
+        stack.push(Operand::Value(val));
+        Ok(())
     }
 
     pub fn compile(input: Vec<Token>, context: String) -> Expression {
         let mut output = Vec::new();
         let mut stack = Vec::new();
+        let legacy = session::settings().legacy_precedence;
 
         for token in input {
             match token.kind() {
-                TokenKind::Value(_) | TokenKind::Identifier(_) | TokenKind::FunctionCall(_) => {
+                TokenKind::Value(_)
+                | TokenKind::Bits(_)
+                | TokenKind::Identifier(_)
+                | TokenKind::FunctionCall(_)
+                | TokenKind::Conditional(_)
+                | TokenKind::List(_)
+                | TokenKind::Comprehension(_)
+                | TokenKind::FormatString(_)
+                | TokenKind::Lambda(_) => {
                     output.push(token);
                 }
+                // the operand an index applies to is already complete in the output
+                TokenKind::Index(_) => output.push(token),
                 TokenKind::Operator(_) => {
-                    Expression::process_operator(token, &mut stack, &mut output);
+                    Expression::process_operator(token, &mut stack, &mut output, legacy);
                 }
                 TokenKind::Parenthesis(_) => {
                     Expression::process_parenthesis(token, &mut stack, &mut output);
@@ -285,8 +392,8 @@ impl Expression {
             }
         }
 
-        while stack.len() > 0 {
-            output.push(stack.pop().unwrap());
+        while let Some(token) = stack.pop() {
+            output.push(token);
         }
 
         Expression {
@@ -295,21 +402,36 @@ impl Expression {
         }
     }
 
-    fn process_operator(token: Token, stack: &mut Vec<Token>, output: &mut Vec<Token>) {
-        let (precedence, left_assoc) = match token.kind() {
-            TokenKind::Operator(op) => (op.precedence(), op.is_left_associative()),
+    fn process_operator(
+        token: Token,
+        stack: &mut Vec<Token>,
+        output: &mut Vec<Token>,
+        legacy: bool,
+    ) {
+        let (precedence, left_assoc, unary) = match token.kind() {
+            TokenKind::Operator(op) => (
+                op.precedence(legacy),
+                op.is_left_associative(legacy),
+                op.is_unary(),
+            ),
             _ => panic!("Token is not an operator"),
         };
 
+        // prefix operators have no left operand, so nothing can be reduced before them
+        if unary {
+            stack.push(token);
+            return;
+        }
+
         if let Some(top_of_stack) = stack.last() {
             match top_of_stack.kind() {
                 TokenKind::Parenthesis(Parenthesis::Left) => stack.push(token),
                 TokenKind::Operator(op) => {
-                    if op.precedence() < precedence {
+                    if op.precedence(legacy) < precedence {
                         stack.push(token);
-                    } else if op.precedence() > precedence {
+                    } else if op.precedence(legacy) > precedence {
                         output.push(stack.pop().unwrap());
-                        Expression::process_operator(token, stack, output);
+                        Expression::process_operator(token, stack, output, legacy);
                     } else {
                         if left_assoc {
                             output.push(stack.pop().unwrap());
@@ -343,157 +465,3 @@ impl Expression {
         }
     }
 }
-
-trait Maths {
-    fn to_radians(&self) -> Self;
-    fn to_degrees(&self) -> Self;
-
-    fn trig_sin(&self, context: &str, pos: usize) -> Result<Decimal, instruction::Error>;
-    fn trig_cos(&self, context: &str, pos: usize) -> Result<Decimal, instruction::Error>;
-    fn trig_tan(&self, context: &str, pos: usize) -> Result<Decimal, instruction::Error>;
-    fn trig_tan_deg(&self, context: &str, pos: usize) -> Result<Decimal, instruction::Error>;
-
-    fn trig_asin(&self, context: &str, pos: usize) -> Result<Decimal, instruction::Error>;
-    fn trig_acos(&self, context: &str, pos: usize) -> Result<Decimal, instruction::Error>;
-    fn trig_atan(&self, context: &str, pos: usize) -> Result<Decimal, instruction::Error>;
-
-    fn math_ln(&self, context: &str, pos: usize) -> Result<Decimal, instruction::Error>;
-    fn math_log(&self, context: &str, pos: usize) -> Result<Decimal, instruction::Error>;
-    fn math_pow(&self, rhs: Decimal, context: &str, pos: usize) -> Result<Decimal, instruction::Error>;
-}
-
-impl Maths for Decimal {
-    fn to_radians(&self) -> Decimal {
-        self * (Decimal::PI / Decimal::from_isize(180).unwrap())
-    }
-
-    fn to_degrees(&self) -> Decimal {
-        self * (Decimal::from_isize(180).unwrap() / Decimal::PI)
-    }
-
-    fn trig_sin(&self, context: &str, pos: usize) -> Result<Decimal, instruction::Error> {
-        match self.checked_sin() {
-            Some(value) => Ok(value),
-            None => Err(instruction::Error::new(
-                context.to_string(),
-                pos,
-                instruction::ErrorKind::MathError,
-            )),
-        }
-    }
-
-    fn trig_cos(&self, context: &str, pos: usize) -> Result<Decimal, instruction::Error> {
-        match self.checked_cos() {
-            Some(value) => Ok(value),
-            None => Err(instruction::Error::new(
-                context.to_string(),
-                pos,
-                instruction::ErrorKind::MathError,
-            )),
-        }
-    }
-
-    fn trig_tan(&self, context: &str, pos: usize) -> Result<Decimal, instruction::Error> {
-        if self.abs() % Decimal::HALF_PI == Decimal::ZERO {
-            return Err(instruction::Error::new(
-                context.to_string(),
-                pos,
-                instruction::ErrorKind::MathError,
-            ));
-        }
-
-        match self.checked_tan() {
-            Some(value) => Ok(value),
-            None => Err(instruction::Error::new(
-                context.to_string(),
-                pos,
-                instruction::ErrorKind::MathError,
-            )),
-        }
-    }
-
-    fn trig_tan_deg(&self, context: &str, pos: usize) -> Result<Decimal, instruction::Error> {
-        if self.abs() % Decimal::from_isize(180).unwrap() == Decimal::from_isize(90).unwrap() {
-            return Err(instruction::Error::new(
-                context.to_string(),
-                pos,
-                instruction::ErrorKind::MathError,
-            ));
-        }
-
-        match self.to_radians().checked_tan() {
-            Some(value) => Ok(value),
-            None => Err(instruction::Error::new(
-                context.to_string(),
-                pos,
-                instruction::ErrorKind::MathError,
-            )),
-        }
-    }
-
-    fn trig_asin(&self, context: &str, pos: usize) -> Result<Decimal, instruction::Error> {
-        match Decimal::from_f64(self.to_f64().unwrap().asin()) {
-            Some(value) => Ok(value),
-            None => Err(instruction::Error::new(
-                context.to_string(),
-                pos,
-                instruction::ErrorKind::MathError,
-            )),
-        }
-    }
-
-    fn trig_acos(&self, context: &str, pos: usize) -> Result<Decimal, instruction::Error> {
-        match Decimal::from_f64(self.to_f64().unwrap().acos()) {
-            Some(value) => Ok(value),
-            None => Err(instruction::Error::new(
-                context.to_string(),
-                pos,
-                instruction::ErrorKind::MathError,
-            )),
-        }
-    }
-
-    fn trig_atan(&self, context: &str, pos: usize) -> Result<Decimal, instruction::Error> {
-        match Decimal::from_f64(self.to_f64().unwrap().atan()) {
-            Some(value) => Ok(value),
-            None => Err(instruction::Error::new(
-                context.to_string(),
-                pos,
-                instruction::ErrorKind::MathError,
-            )),
-        }
-    }
-
-    fn math_ln(&self, context: &str, pos: usize) -> Result<Decimal, instruction::Error> {
-        match self.checked_ln() {
-            Some(value) => Ok(value),
-            None => Err(instruction::Error::new(
-                context.to_string(),
-                pos,
-                instruction::ErrorKind::MathError,
-            )),
-        }
-    }
-
-    fn math_log(&self, context: &str, pos: usize) -> Result<Decimal, instruction::Error> {
-        match self.checked_log10() {
-            Some(value) => Ok(value),
-            None => Err(instruction::Error::new(
-                context.to_string(),
-                pos,
-                instruction::ErrorKind::MathError,
-            )),
-        }
-    }
-
-    fn math_pow(&self, rhs: Decimal, context: &str, pos: usize) -> Result<Decimal, instruction::Error> {
-        match self.checked_powd(rhs) {
-            Some(value) => Ok(value),
-            None => Err(instruction::Error::new(
-                context.to_string(),
-                pos,
-                instruction::ErrorKind::InvalidExponent,
-            )),
-        }
-    }
-}
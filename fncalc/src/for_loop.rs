@@ -0,0 +1,77 @@
+use crate::expression::Expression;
+use crate::instruction::{self, Instruction, ReturnValue};
+use crate::list;
+use crate::session;
+
+// the variable is set in the current namespace, and keeps the last element after the loop
+#[derive(Debug, Clone)]
+pub struct ForLoop {
+    context: String,
+    variable: String,
+    iterable: Expression,
+    // the position of the iterable in the context
+    iterable_pos: usize,
+    body: Vec<Instruction>,
+}
+
+impl std::fmt::Display for ForLoop {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.context)
+    }
+}
+
+impl ForLoop {
+    pub fn new(
+        context: String,
+        variable: String,
+        iterable: Expression,
+        iterable_pos: usize,
+        body: Vec<Instruction>,
+    ) -> ForLoop {
+        ForLoop {
+            context,
+            variable,
+            iterable,
+            iterable_pos,
+            body,
+        }
+    }
+
+    pub fn exec(&self, output_stream: &mut String) -> instruction::Result {
+        let mut result = None;
+        let iterable = match self.iterable.exec(output_stream) {
+            Ok(value) => value.expect("Expressions should always return a value on success"),
+            Err(e) => return Err(e),
+        };
+
+        'main_loop: for value in list::items(iterable, &self.context, self.iterable_pos)? {
+            session::set_variable(&self.variable, value);
+
+            for item in &self.body {
+                match item.exec(output_stream) {
+                    Ok(return_value) => match return_value {
+                        ReturnValue::Value(value) => result = Some(value),
+                        ReturnValue::Return(value) => {
+                            return Ok(ReturnValue::Return(value));
+                        }
+                        ReturnValue::TailCall(tail_call) => {
+                            return Ok(ReturnValue::TailCall(tail_call));
+                        }
+                        ReturnValue::Break => {
+                            break 'main_loop;
+                        }
+                        ReturnValue::None => (),
+                    },
+                    Err(e) => {
+                        return Err(e);
+                    }
+                }
+            }
+        }
+
+        match result {
+            Some(value) => Ok(ReturnValue::Value(value)),
+            None => Ok(ReturnValue::None),
+        }
+    }
+}
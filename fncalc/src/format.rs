@@ -0,0 +1,261 @@
+use crate::expression::Expression;
+use crate::instruction::{self, ErrorKind};
+use crate::precise;
+use crate::value::Value;
+use rust_decimal::RoundingStrategy;
+
+// the largest width and number of decimal places of a format specification
+const MAX_WIDTH: usize = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Align {
+    Left,
+    Right,
+    Center,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Sign {
+    Negative,
+    Always,
+    Space,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    Fixed,
+    Exponent,
+    Decimal,
+    Hex,
+    Binary,
+    Octal,
+}
+
+// how a value is written in an f-string or by 'format', like in Python:
+// '[[fill]align][sign][#][0][width][.precision][type]'
+#[derive(Debug, Clone)]
+pub struct Spec {
+    fill: char,
+    align: Option<Align>,
+    sign: Sign,
+    prefix: bool,
+    zero: bool,
+    width: usize,
+    precision: Option<usize>,
+    kind: Option<Kind>,
+}
+
+impl Spec {
+    // None if the specification is invalid
+    pub fn parse(spec: &str) -> Option<Spec> {
+        let align = |c: char| match c {
+            '<' => Some(Align::Left),
+            '>' => Some(Align::Right),
+            '^' => Some(Align::Center),
+            _ => None,
+        };
+        let mut chars = spec.chars().peekable();
+        let mut result = Spec {
+            fill: ' ',
+            align: None,
+            sign: Sign::Negative,
+            prefix: false,
+            zero: false,
+            width: 0,
+            precision: None,
+            kind: None,
+        };
+
+        let mut start = spec.chars();
+        let (first, second) = (start.next(), start.next());
+        if let (Some(fill), Some(second)) = (first, second.and_then(align)) {
+            result.fill = fill;
+            result.align = Some(second);
+            chars.nth(1);
+        } else if let Some(first) = first.and_then(align) {
+            result.align = Some(first);
+            chars.next();
+        }
+
+        if let Some(sign) = chars.next_if(|c| matches!(c, '+' | ' ' | '-')) {
+            result.sign = match sign {
+                '+' => Sign::Always,
+                ' ' => Sign::Space,
+                _ => Sign::Negative,
+            };
+        }
+        result.prefix = chars.next_if_eq(&'#').is_some();
+        result.zero = chars.next_if_eq(&'0').is_some();
+
+        let number = |chars: &mut std::iter::Peekable<std::str::Chars>| {
+            let mut digits = String::new();
+            while let Some(digit) = chars.next_if(|c| c.is_ascii_digit()) {
+                digits.push(digit);
+            }
+            digits.parse::<usize>().ok().filter(|n| *n <= MAX_WIDTH)
+        };
+        if chars.peek().is_some_and(|c| c.is_ascii_digit()) {
+            result.width = number(&mut chars)?;
+        }
+        if chars.next_if_eq(&'.').is_some() {
+            result.precision = Some(number(&mut chars)?);
+        }
+
+        result.kind = match chars.next() {
+            Some('f') => Some(Kind::Fixed),
+            Some('e') => Some(Kind::Exponent),
+            Some('d') => Some(Kind::Decimal),
+            Some('x') => Some(Kind::Hex),
+            Some('b') => Some(Kind::Binary),
+            Some('o') => Some(Kind::Octal),
+            Some(_) => return None,
+            None => None,
+        };
+        if chars.next().is_some() {
+            return None;
+        }
+        Some(result)
+    }
+
+    // strings, lists and functions can only be padded, the types only apply to numbers
+    pub fn apply(
+        &self,
+        value: &Value,
+        context: &str,
+        pos: usize,
+    ) -> Result<String, instruction::Error> {
+        if let Value::String(_) | Value::List(_) | Value::Function(_) = value {
+            if self.kind.is_some()
+                || self.precision.is_some()
+                || self.sign != Sign::Negative
+                || self.prefix
+                || self.zero
+            {
+                return Err(error(context, pos, ErrorKind::TypeError));
+            }
+            let text = crate::format_value(value.clone());
+            return Ok(self.pad("", "", &text, Align::Left));
+        }
+
+        let integer = |radix: fn(u128) -> String| {
+            let x = value.to_integer(context, pos)?;
+            let sign = if x < 0 { "-" } else { "" };
+            Ok::<_, instruction::Error>(format!("{sign}{}", radix(x.unsigned_abs())))
+        };
+        let (text, prefix) = match (self.kind, self.precision) {
+            (None, None) => (crate::format_value(value.clone()), ""),
+            (None | Some(Kind::Fixed), precision) => {
+                (fixed(value, precision.unwrap_or(6), context, pos)?, "")
+            }
+            (Some(Kind::Exponent), precision) => {
+                let x = value.float(context, pos)?;
+                (format!("{x:.*e}", precision.unwrap_or(6)), "")
+            }
+            (Some(Kind::Decimal), _) => (integer(|x| x.to_string())?, ""),
+            (Some(Kind::Hex), _) => (integer(|x| format!("{x:x}"))?, "0x"),
+            (Some(Kind::Binary), _) => (integer(|x| format!("{x:b}"))?, "0b"),
+            (Some(Kind::Octal), _) => (integer(|x| format!("{x:o}"))?, "0o"),
+        };
+        let prefix = if self.prefix { prefix } else { "" };
+
+        let (sign, text) = match text.strip_prefix('-') {
+            Some(text) => ("-", text),
+            None => match self.sign {
+                Sign::Always => ("+", text.as_str()),
+                Sign::Space => (" ", text.as_str()),
+                Sign::Negative => ("", text.as_str()),
+            },
+        };
+        Ok(self.pad(sign, prefix, text, Align::Right))
+    }
+
+    // zeros go between the sign and the digits, unless the alignment is given
+    fn pad(&self, sign: &str, prefix: &str, text: &str, default: Align) -> String {
+        let len = sign.len() + prefix.len() + text.chars().count();
+        let missing = self.width.saturating_sub(len);
+        if self.zero && self.align.is_none() {
+            return format!("{sign}{prefix}{}{text}", "0".repeat(missing));
+        }
+
+        let fill = |n: usize| self.fill.to_string().repeat(n);
+        let text = format!("{sign}{prefix}{text}");
+        match self.align.unwrap_or(default) {
+            Align::Left => format!("{text}{}", fill(missing)),
+            Align::Right => format!("{}{text}", fill(missing)),
+            Align::Center => format!("{}{text}{}", fill(missing / 2), fill(missing - missing / 2)),
+        }
+    }
+}
+
+// the value with the given number of decimal places, floats are rounded like by Rust
+// and everything else half away from zero
+fn fixed(
+    value: &Value,
+    precision: usize,
+    context: &str,
+    pos: usize,
+) -> Result<String, instruction::Error> {
+    match value {
+        Value::Float(x) => Ok(format!("{x:.precision$}")),
+        Value::Precise(x) => Ok(precise::fixed(x, precision as i64)),
+        _ => {
+            let x = value.real(context, pos)?;
+            let x = x.round_dp_with_strategy(
+                precision.min(28) as u32,
+                RoundingStrategy::MidpointAwayFromZero,
+            );
+            Ok(format!("{x:.precision$}"))
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Part {
+    Text(String),
+    // the position of the expression in the f-string, for errors in its format
+    Field(Expression, Spec, usize),
+}
+
+// 'f"{x:.2f} m/s"', the expressions are evaluated in the current namespace
+#[derive(Debug, Clone)]
+pub struct FormatString {
+    context: String,
+    parts: Vec<Part>,
+}
+
+impl std::fmt::Display for FormatString {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.context)
+    }
+}
+
+impl FormatString {
+    pub fn new(context: String, parts: Vec<Part>) -> FormatString {
+        FormatString { context, parts }
+    }
+
+    pub fn exec(
+        &self,
+        context: &str,
+        pos: usize,
+        output_stream: &mut String,
+    ) -> Result<Value, instruction::Error> {
+        let mut result = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Text(text) => result.push_str(text),
+                Part::Field(expression, spec, offset) => {
+                    let value = expression
+                        .exec(output_stream)?
+                        .expect("Expressions should always return a value on success");
+                    result.push_str(&spec.apply(&value, context, pos + offset)?);
+                }
+            }
+        }
+        Ok(Value::String(result))
+    }
+}
+
+fn error(context: &str, pos: usize, kind: ErrorKind) -> instruction::Error {
+    instruction::Error::new(context.to_string(), pos, kind)
+}
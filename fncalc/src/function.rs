@@ -1,12 +1,41 @@
 #![allow(dead_code)]
+use crate::builtin::Builtin;
 use crate::expression::Expression;
-use crate::instruction::{self, Instruction, ReturnValue};
+use crate::instruction::{self, ErrorKind, Instruction, ReturnValue};
+use crate::session;
+use crate::value::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
 
+// a default value is evaluated in the namespace of the call, after the arguments before it
+#[derive(Debug, Clone)]
+pub struct Parameter {
+    name: String,
+    default: Option<Expression>,
+}
+
+impl Parameter {
+    pub fn new(name: String, default: Option<Expression>) -> Parameter {
+        Parameter { name, default }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn default(&self) -> Option<&Expression> {
+        self.default.as_ref()
+    }
+}
+
+// lambdas have no name, and a variadic parameter takes the remaining arguments as a list
 #[derive(Debug, Clone)]
 pub struct Function {
+    name: Option<String>,
     context: String,
     body: Vec<Instruction>,
-    argument_names: Vec<String>,
+    parameters: Vec<Parameter>,
+    variadic: Option<String>,
 }
 
 impl std::fmt::Display for Function {
@@ -16,16 +45,102 @@ impl std::fmt::Display for Function {
 }
 
 impl Function {
-    pub fn new(context: String, body: Vec<Instruction>, argument_names: Vec<String>) -> Function {
+    pub fn new(
+        name: Option<String>,
+        context: String,
+        body: Vec<Instruction>,
+        parameters: Vec<Parameter>,
+        variadic: Option<String>,
+    ) -> Function {
         Function {
+            name,
             context,
             body,
-            argument_names,
+            parameters,
+            variadic,
+        }
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub fn parameters(&self) -> &Vec<Parameter> {
+        &self.parameters
+    }
+
+    // the smallest and the largest number of arguments, None if there is no limit
+    fn arity(&self) -> (usize, Option<usize>) {
+        let required = self
+            .parameters
+            .iter()
+            .filter(|p| p.default.is_none())
+            .count();
+        match self.variadic {
+            Some(_) => (required, None),
+            None => (required, Some(self.parameters.len())),
         }
     }
 
-    pub fn argument_names(&self) -> &Vec<String> {
-        &self.argument_names
+    pub fn accepts(&self, count: usize) -> bool {
+        let (min, max) = self.arity();
+        min <= count && max.is_none_or(|max| count <= max)
+    }
+
+    // overloads with the same parameters replace each other
+    pub fn overloads(&self, other: &Function) -> bool {
+        self.parameters.len() == other.parameters.len()
+            && self.variadic.is_some() == other.variadic.is_some()
+    }
+
+    // like '1', '1 to 2' or '2 or more'
+    fn describe_arity(&self) -> String {
+        match self.arity() {
+            (min, None) => format!("{min} or more"),
+            (min, Some(max)) if min == max => format!("{min}"),
+            (min, Some(max)) => format!("{min} to {max}"),
+        }
+    }
+
+    // the values of the parameters given by the arguments, and the parameters
+    // which were left out and take their default value
+    pub fn bind(
+        &self,
+        arguments: &[Value],
+        named_arguments: &[(String, Value)],
+    ) -> Result<(HashMap<String, Value>, Vec<&Parameter>), ErrorKind> {
+        if arguments.len() > self.parameters.len() && self.variadic.is_none() {
+            return Err(ErrorKind::ExpectedArguments(self.describe_arity()));
+        }
+
+        let mut values: HashMap<String, Value> = HashMap::new();
+        for (parameter, argument) in self.parameters.iter().zip(arguments) {
+            values.insert(parameter.name.clone(), argument.clone());
+        }
+        if let Some(variadic) = &self.variadic {
+            let rest = arguments.get(self.parameters.len()..).unwrap_or_default();
+            values.insert(variadic.clone(), Value::List(rest.to_vec()));
+        }
+
+        for (name, value) in named_arguments {
+            let is_parameter = self.parameters.iter().any(|p| &p.name == name);
+            if !is_parameter || values.contains_key(name) {
+                return Err(ErrorKind::UnexpectedArgument(name.clone()));
+            }
+            values.insert(name.clone(), value.clone());
+        }
+
+        let mut defaults = Vec::new();
+        for parameter in &self.parameters {
+            if values.contains_key(&parameter.name) {
+                continue;
+            }
+            match parameter.default {
+                Some(_) => defaults.push(parameter),
+                None => return Err(ErrorKind::MissingArgument(parameter.name.clone())),
+            }
+        }
+        Ok((values, defaults))
     }
 
     pub fn exec(&self, output_stream: &mut String) -> instruction::Result {
@@ -37,6 +152,9 @@ impl Function {
                     ReturnValue::Return(value) => {
                         return Ok(ReturnValue::Value(value));
                     }
+                    ReturnValue::TailCall(tail_call) => {
+                        return Ok(ReturnValue::TailCall(tail_call));
+                    }
                     ReturnValue::None => (),
                     ReturnValue::Break => {
                         unreachable!("Function calls should not return Break or Return type")
@@ -55,26 +173,127 @@ impl Function {
     }
 }
 
+// the overload taking exactly the given number of arguments,
+// or else the one with the fewest parameters which accepts it
+pub fn overload(overloads: &[Overload], count: usize) -> Result<&Overload, ErrorKind> {
+    let exact = overloads
+        .iter()
+        .find(|o| o.function.variadic.is_none() && o.function.parameters.len() == count);
+    match exact.or_else(|| overloads.iter().find(|o| o.function.accepts(count))) {
+        Some(overload) => Ok(overload),
+        None => {
+            let mut arities: Vec<String> =
+                overloads.iter().map(|o| o.function.describe_arity()).collect();
+            let last = arities.pop().unwrap_or_default();
+            let arities = match arities.is_empty() {
+                true => last,
+                false => format!("{} or {last}", arities.join(", ")),
+            };
+            Err(ErrorKind::ExpectedArguments(arities))
+        }
+    }
+}
+
+// what a function captured when it was created: the variables of the namespaces around it,
+// innermost first, which are shared with those namespaces until either side changes them,
+// and the functions defined in the call around it, which are looked up when it is called
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Environment {
+    pub variables: Vec<Arc<HashMap<String, Value>>>,
+    pub scope: Option<Arc<session::Scope>>,
+}
+
+// one of the functions with the same name, with what it captured when it was defined
+#[derive(Debug, Clone)]
+pub struct Overload {
+    pub function: Arc<Function>,
+    pub environment: Environment,
+}
+
+impl PartialEq for Overload {
+    fn eq(&self, other: &Overload) -> bool {
+        Arc::ptr_eq(&self.function, &other.function) && self.environment == other.environment
+    }
+}
+
+// a function as a value, together with what it captured when it was created.
+// Named functions are written as their name and lambdas as their source.
+// A function defined with several numbers of parameters is a set of overloads,
+// sorted by their number of parameters.
+#[derive(Debug, Clone)]
+pub enum Callable {
+    Builtin(&'static Builtin),
+    Function(Arc<Function>, Environment),
+    Overloaded(String, Vec<Overload>),
+}
+
+impl Callable {
+    // a lambda evaluated now, which captures the current namespace
+    pub fn capture(function: &Arc<Function>) -> Callable {
+        Callable::Function(function.clone(), session::environment())
+    }
+}
+
+// closures of the same function are only equal if they captured the same values
+impl PartialEq for Callable {
+    fn eq(&self, other: &Callable) -> bool {
+        match (self, other) {
+            (Callable::Builtin(lhs), Callable::Builtin(rhs)) => std::ptr::eq(*lhs, *rhs),
+            (Callable::Function(lhs, lhs_captured), Callable::Function(rhs, rhs_captured)) => {
+                Arc::ptr_eq(lhs, rhs) && lhs_captured == rhs_captured
+            }
+            (Callable::Overloaded(_, lhs), Callable::Overloaded(_, rhs)) => lhs == rhs,
+            _ => false,
+        }
+    }
+}
+
+impl std::fmt::Display for Callable {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Callable::Builtin(builtin) => write!(f, "{}", builtin.name()),
+            Callable::Function(function, _) => match function.name() {
+                Some(name) => write!(f, "{name}"),
+                None => write!(f, "{}", function.context),
+            },
+            Callable::Overloaded(name, _) => write!(f, "{name}"),
+        }
+    }
+}
+
+// a call with its evaluated arguments, which hasn't been made yet
+#[derive(Debug, Clone)]
+pub struct PendingCall {
+    pub function: Callable,
+    pub arguments: Vec<Value>,
+    pub named_arguments: Vec<(String, Value)>,
+    pub context: String,
+    pub pos: usize,
+}
+
 #[derive(Debug, Clone)]
 pub struct FunctionCall {
     context: String,
     name: String,
     arguments: Vec<Expression>,
+    named_arguments: Vec<(String, Expression)>,
 }
 
 impl FunctionCall {
-    pub fn new(context: String, name: String, arguments: Vec<Expression>) -> FunctionCall {
+    pub fn new(
+        context: String,
+        name: String,
+        arguments: Vec<Expression>,
+        named_arguments: Vec<(String, Expression)>,
+    ) -> FunctionCall {
         FunctionCall {
             context,
             name,
             arguments,
+            named_arguments,
         }
     }
 
-    pub fn context(&self) -> &str {
-        &self.context
-    }
-
     pub fn name(&self) -> &str {
         &self.name
     }
@@ -82,6 +301,10 @@ impl FunctionCall {
     pub fn arguments(&self) -> &Vec<Expression> {
         &self.arguments
     }
+
+    pub fn named_arguments(&self) -> &Vec<(String, Expression)> {
+        &self.named_arguments
+    }
 }
 
 impl std::fmt::Display for FunctionCall {
@@ -90,27 +313,37 @@ impl std::fmt::Display for FunctionCall {
     }
 }
 
+// several values are returned as a list
 #[derive(Debug, Clone)]
 pub struct Return {
     context: String,
-    expr: Expression,
+    exprs: Vec<Expression>,
 }
 
 impl Return {
-    pub fn new(context: String, expr: Expression) -> Return {
-        Return { context, expr }
-    }
-
-    pub fn context(&self) -> &str {
-        &self.context
+    pub fn new(context: String, exprs: Vec<Expression>) -> Return {
+        Return { context, exprs }
     }
 
+    // 'return f(x)' in a function is a tail call, which is made after the function returned
     pub fn exec(&self, output_stream: &mut String) -> instruction::Result {
-        match self.expr.exec(output_stream) {
-            Ok(output) => Ok(ReturnValue::Return(
-                output.expect("Expressions should always return a value on success"),
-            )),
-            Err(e) => Err(e),
+        if let [expr] = self.exprs.as_slice() {
+            if let Some((fncall, pos)) = expr.function_call().filter(|_| session::in_function()) {
+                let tail_call = session::prepare_call(fncall, expr.context(), pos, output_stream)?;
+                return Ok(ReturnValue::TailCall(Box::new(tail_call)));
+            }
+        }
+
+        let mut values = Vec::new();
+        for expr in &self.exprs {
+            values.push(
+                expr.exec(output_stream)?
+                    .expect("Expressions should always return a value on success"),
+            );
+        }
+        match values.len() {
+            1 => Ok(ReturnValue::Return(values.remove(0))),
+            _ => Ok(ReturnValue::Return(Value::List(values))),
         }
     }
 }
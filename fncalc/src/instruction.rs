@@ -1,38 +1,84 @@
 use crate::branch::Branch;
+use crate::constant;
 use crate::expression::Expression;
-use crate::function::Return;
+use crate::for_loop::ForLoop;
+use crate::function::{Function, PendingCall, Return};
+use crate::session::{self, Mode};
 use crate::while_loop::WhileLoop;
 use crate::Value;
+use crate::MAX_PRECISION;
+use std::sync::Arc;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub enum ErrorKind {
     SyntaxError,
     InvalidNumberLiteral,
+    // a number of digits for the precise mode which is not in 1..=MAX_PRECISION
+    InvalidPrecision,
+    OutOfRange,
     ZeroDivision,
     MathError,
+    DomainError,
+    NotReal,
     InvalidExponent,
     IdentifierNotFound,
     InvalidAssignment,
     MissingReturnValue,
     InvalidNumberOfArgument,
+    // the parameter without a default value which wasn't given
+    MissingArgument(String),
+    // the name of an argument which is not a parameter or was given twice
+    UnexpectedArgument(String),
+    // how many arguments the function takes, like '1 or 2'
+    ExpectedArguments(String),
     IterationLimitReached,
+    TypeError,
+    IndexOutOfRange,
+    InvalidFormat,
 }
 
 impl std::fmt::Display for ErrorKind {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
             ErrorKind::SyntaxError => write!(f, "Syntax error"),
-            ErrorKind::InvalidNumberLiteral => write!(f, "Invalid number"),
+            ErrorKind::InvalidNumberLiteral => write!(f, "Number literal out of range"),
+            ErrorKind::InvalidPrecision => {
+                write!(f, "Precision must be between 1 and {MAX_PRECISION}")
+            }
+            ErrorKind::OutOfRange => write!(f, "Value out of range"),
             ErrorKind::ZeroDivision => write!(f, "Division by zero"),
             ErrorKind::MathError => write!(f, "Math error"),
+            ErrorKind::DomainError => write!(f, "Argument outside of the function's domain"),
+            ErrorKind::NotReal => write!(f, "Expected a real number"),
             ErrorKind::InvalidExponent => write!(f, "Invalid exponent"),
             ErrorKind::IdentifierNotFound => write!(f, "Identifier not found"),
             ErrorKind::InvalidAssignment => write!(f, "Invalid assignment"),
             ErrorKind::MissingReturnValue => write!(f, "Function did not return a value"),
             ErrorKind::IterationLimitReached => write!(f, "Maximum iteration count reached"),
+            ErrorKind::TypeError => write!(f, "Wrong type of value"),
+            ErrorKind::IndexOutOfRange => write!(f, "Index out of range"),
+            ErrorKind::InvalidFormat => write!(f, "Invalid format specification"),
             ErrorKind::InvalidNumberOfArgument => {
                 write!(f, "Invalid number of arguments passed to function")
             }
+            ErrorKind::MissingArgument(name) => {
+                write!(
+                    f,
+                    "Invalid number of arguments passed to function, '{name}' is missing"
+                )
+            }
+            ErrorKind::ExpectedArguments(arities) => {
+                write!(
+                    f,
+                    "Invalid number of arguments passed to function, expected {arities}"
+                )
+            }
+            ErrorKind::UnexpectedArgument(name) => {
+                write!(
+                    f,
+                    "Invalid number of arguments passed to function, unexpected '{name}'"
+                )
+            }
         }
     }
 }
@@ -53,8 +99,8 @@ impl Error {
         self.pos
     }
 
-    pub fn kind(&self) -> ErrorKind {
-        self.kind
+    pub fn kind(&self) -> &ErrorKind {
+        &self.kind
     }
 }
 
@@ -67,7 +113,7 @@ impl std::fmt::Display for Error {
             result.push('\n');
 
             for _ in 0..self.pos() {
-                result.push_str(" ");
+                result.push(' ');
             }
             result.push_str("^\n");
             result.push_str(&format!("Error: {}", self.kind()));
@@ -79,10 +125,11 @@ impl std::fmt::Display for Error {
 
 impl std::error::Error for Error {}
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub enum ReturnValue {
     Value(Value),
     Return(Value),
+    TailCall(Box<PendingCall>),
     None,
     Break,
 }
@@ -103,8 +150,13 @@ pub enum Instruction {
     Expression(Expression),
     Branch(Branch),
     WhileLoop(WhileLoop),
+    ForLoop(ForLoop),
     Return(Return),
     Print(Print),
+    Destructuring(Destructuring),
+    Mode(Mode),
+    // a function defined in another one, which is stored in a variable
+    Function(Arc<Function>),
     Break,
 }
 
@@ -114,8 +166,18 @@ impl Instruction {
             Instruction::Expression(e) => e.exec(output_stream),
             Instruction::Branch(b) => b.exec(output_stream),
             Instruction::WhileLoop(l) => l.exec(output_stream),
+            Instruction::ForLoop(l) => l.exec(output_stream),
             Instruction::Return(r) => r.exec(output_stream),
             Instruction::Print(p) => p.exec(output_stream),
+            Instruction::Destructuring(d) => d.exec(output_stream),
+            Instruction::Mode(mode) => {
+                mode.apply();
+                Ok(ReturnValue::None)
+            }
+            Instruction::Function(function) => {
+                session::define_function(function);
+                Ok(ReturnValue::None)
+            }
             Instruction::Break => Ok(ReturnValue::Break),
         }
     }
@@ -127,8 +189,12 @@ impl std::fmt::Display for Instruction {
             Instruction::Expression(e) => write!(f, "{e}"),
             Instruction::Branch(b) => write!(f, "{b}"),
             Instruction::WhileLoop(l) => write!(f, "{l}"),
+            Instruction::ForLoop(l) => write!(f, "{l}"),
             Instruction::Return(r) => write!(f, "{r}"),
             Instruction::Print(p) => write!(f, "{p}"),
+            Instruction::Destructuring(d) => write!(f, "{d}"),
+            Instruction::Mode(mode) => write!(f, "{mode}"),
+            Instruction::Function(function) => write!(f, "{function}"),
             Instruction::Break => write!(f, "break"),
         }
     }
@@ -136,31 +202,95 @@ impl std::fmt::Display for Instruction {
 
 #[derive(Debug, Clone)]
 pub struct Print {
-    expr: Expression,
+    exprs: Vec<Expression>,
 }
 
 impl Print {
-    pub fn new(expr: Expression) -> Print {
-        Print { expr }
+    pub fn new(exprs: Vec<Expression>) -> Print {
+        Print { exprs }
     }
 
+    // the values are printed on one line, separated by spaces
     pub fn exec(&self, output_stream: &mut String) -> Result {
-        match self.expr.exec(output_stream) {
-            Ok(output) => {
-                output_stream.push_str(
-                    &(crate::format_value(
-                        output.expect("Expressions should always return a value on success"),
-                    ) + "\n"),
-                );
-                Ok(ReturnValue::None)
-            }
-            Err(e) => Err(e),
+        let mut values = Vec::new();
+        for expr in &self.exprs {
+            let value = expr
+                .exec(output_stream)?
+                .expect("Expressions should always return a value on success");
+            values.push(crate::format_value(value));
         }
+        output_stream.push_str(&(values.join(" ") + "\n"));
+        Ok(ReturnValue::None)
     }
 }
 
 impl std::fmt::Display for Print {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "print {}", self.expr)
+        let exprs: Vec<String> = self.exprs.iter().map(|expr| expr.to_string()).collect();
+        write!(f, "print {}", exprs.join(", "))
+    }
+}
+
+// 'q, r = divmod(17, 5)' assigns the elements of a list of the same length,
+// and 'a, b = b, a' the values, which are all evaluated before the first assignment
+#[derive(Debug, Clone)]
+pub struct Destructuring {
+    context: String,
+    // the positions of the names in the context, for errors
+    names: Vec<(String, usize)>,
+    exprs: Vec<Expression>,
+}
+
+impl Destructuring {
+    pub fn new(
+        context: String,
+        names: Vec<(String, usize)>,
+        exprs: Vec<Expression>,
+    ) -> Destructuring {
+        Destructuring {
+            context,
+            names,
+            exprs,
+        }
+    }
+
+    pub fn exec(&self, output_stream: &mut String) -> Result {
+        let mut values = Vec::new();
+        for expr in &self.exprs {
+            values.push(
+                expr.exec(output_stream)?
+                    .expect("Expressions should always return a value on success"),
+            );
+        }
+        let values = match (values.len(), values.first()) {
+            (1, Some(Value::List(elements))) => elements.clone(),
+            (1, _) => return Err(self.error(0, ErrorKind::TypeError)),
+            _ => values,
+        };
+
+        if values.len() != self.names.len() {
+            return Err(self.error(0, ErrorKind::InvalidAssignment));
+        }
+        let constant = self
+            .names
+            .iter()
+            .find(|(name, _)| constant::get(name).is_some());
+        if let Some((_, pos)) = constant {
+            return Err(self.error(*pos, ErrorKind::InvalidAssignment));
+        }
+        for ((name, _), value) in self.names.iter().zip(&values) {
+            session::set_variable(name, value.clone());
+        }
+        Ok(ReturnValue::Value(Value::List(values)))
+    }
+
+    fn error(&self, pos: usize, kind: ErrorKind) -> Error {
+        Error::new(self.context.clone(), pos, kind)
+    }
+}
+
+impl std::fmt::Display for Destructuring {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.context)
     }
 }
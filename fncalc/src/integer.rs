@@ -0,0 +1,112 @@
+use crate::instruction::{self, ErrorKind};
+use crate::session::{self, IntegerType, NumberMode, Overflow, Radix};
+
+// the integer type of the integer mode, None in the other number modes
+fn integer_type() -> Option<IntegerType> {
+    match session::settings().number_mode {
+        NumberMode::Integer(integer_type) => Some(integer_type),
+        _ => None,
+    }
+}
+
+// integers left over from the integer mode keep the range of its largest types,
+// so that they always fit into a Decimal
+fn range() -> (i128, i128) {
+    match integer_type() {
+        Some(integer_type) => (integer_type.min(), integer_type.max()),
+        None => (i64::MIN.into(), u64::MAX.into()),
+    }
+}
+
+// the lower bits of the integer as a number of the integer type, like a cast in C
+pub fn wrap(x: i128) -> i128 {
+    match integer_type() {
+        Some(integer_type) => {
+            let bits = integer_type.bits();
+            let truncated = x & ((1 << bits) - 1);
+            if truncated > integer_type.max() {
+                truncated - (1 << bits)
+            } else {
+                truncated
+            }
+        }
+        None => x,
+    }
+}
+
+// hex, binary and octal literals are the bits of the integer type like in C, so that
+// 0xff is -1 as an i8, None if they have more bits than the type and overflows are errors
+pub fn from_bits(x: i128) -> Option<i128> {
+    match integer_type() {
+        Some(integer_type) if (0..1 << integer_type.bits()).contains(&x) => Some(wrap(x)),
+        _ => fit(x),
+    }
+}
+
+// None if the integer doesn't fit into the integer type and overflows are errors
+pub fn fit(x: i128) -> Option<i128> {
+    let (min, max) = range();
+    if (min..=max).contains(&x) {
+        Some(x)
+    } else if integer_type().is_some() && session::settings().overflow == Overflow::Wrap {
+        Some(wrap(x))
+    } else {
+        None
+    }
+}
+
+// the checked result of an operation is None if it overflows an i128,
+// the wrapping one still has the right lower bits in that case
+pub fn result(
+    checked: Option<i128>,
+    wrapping: i128,
+    context: &str,
+    pos: usize,
+) -> Result<i128, instruction::Error> {
+    let value = match checked {
+        Some(x) => fit(x),
+        None => fit(wrap(wrapping)).filter(|_| integer_type().is_some()),
+    };
+    value.ok_or_else(|| instruction::Error::new(context.to_string(), pos, ErrorKind::OutOfRange))
+}
+
+// exponentiation by squaring, as a checked and a wrapping result
+pub fn pow(base: i128, exponent: u128) -> (Option<i128>, i128) {
+    let (mut checked, mut wrapping) = (Some(1i128), 1i128);
+    let (mut checked_base, mut wrapping_base) = (Some(base), base);
+    let mut exponent_left = exponent;
+    while exponent_left > 0 {
+        if exponent_left % 2 == 1 {
+            checked = checked
+                .zip(checked_base)
+                .and_then(|(a, b)| a.checked_mul(b));
+            wrapping = wrapping.wrapping_mul(wrapping_base);
+        }
+        exponent_left /= 2;
+        if exponent_left > 0 {
+            checked_base = checked_base.and_then(|b| b.checked_mul(b));
+            wrapping_base = wrapping_base.wrapping_mul(wrapping_base);
+        }
+    }
+    (checked, wrapping)
+}
+
+// in other bases than 10, negative numbers of the integer mode are written
+// as the two's complement of their integer type
+pub fn format(x: i128) -> String {
+    let radix = session::settings().radix;
+    let (sign, digits) = match integer_type() {
+        Some(integer_type) if x < 0 && radix != Radix::Decimal => {
+            ("", (x & ((1 << integer_type.bits()) - 1)).unsigned_abs())
+        }
+        _ if x < 0 => ("-", x.unsigned_abs()),
+        _ => ("", x.unsigned_abs()),
+    };
+
+    match radix {
+        Radix::Decimal => format!("{sign}{digits}"),
+        Radix::Hex => format!("{sign}0x{digits:x}"),
+        Radix::Binary => format!("{sign}0b{digits:b}"),
+        Radix::Octal => format!("{sign}0o{digits:o}"),
+    }
+}
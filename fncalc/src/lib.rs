@@ -1,10 +1,19 @@
 mod branch;
+mod builtin;
+mod constant;
 mod expression;
+mod for_loop;
+mod format;
 mod function;
 mod instruction;
+mod integer;
+mod list;
+mod number;
 mod parser;
+mod precise;
 mod session;
 mod token;
+mod value;
 mod while_loop;
 
 #[cfg(test)]
@@ -12,20 +21,107 @@ mod tests;
 
 use ::rust_decimal::prelude::*;
 
-type Value = Decimal;
-const ONE: Value = Decimal::ONE;
-const NEGATIVE_ONE: Value = Decimal::NEGATIVE_ONE;
-const ZERO: Value = Decimal::ZERO;
-const PI: Value = Decimal::PI;
+use number::Number;
+use value::Value;
+const ONE: Value = Value::Real(Decimal::ONE);
+const ZERO: Value = Value::Real(Decimal::ZERO);
 
 const LOOP_LIMIT: usize = 1_000_000;
 const FUNCTION_CALL_LIMIT: usize = 500;
+// the largest number of significant digits in the precise number mode
+pub const MAX_PRECISION: u64 = 1000;
+// enough for FUNCTION_CALL_LIMIT nested calls, even in debug builds
+const STACK_SIZE: usize = 64 * 1024 * 1024;
+
+pub use builtin::{Arity, Builtin};
+pub use constant::Constant;
+pub use session::{AngleMode, IntegerType, NumberMode, Overflow, Radix};
+
+pub fn builtin_functions() -> &'static [Builtin] {
+    builtin::all()
+}
+
+// includes the physical constants, even if they are not enabled
+pub fn constants() -> &'static [Constant] {
+    constant::all()
+}
+
+pub fn constant(name: &str) -> Option<&'static Constant> {
+    constant::all()
+        .iter()
+        .find(|constant| constant.name() == name)
+}
+
+// makes the physical constants like 'c' or 'N_A' available as read-only values
+pub fn set_physical_constants(enabled: bool) {
+    let mut settings = session::settings();
+    settings.physical_constants = enabled;
+    session::set_settings(settings);
+}
 
 pub fn reset_session() {
     session::clear();
 }
 
+// makes unary minus bind tighter than '^' and '^' left-associative, as in earlier versions
+pub fn set_legacy_precedence(enabled: bool) {
+    let mut settings = session::settings();
+    settings.legacy_precedence = enabled;
+    session::set_settings(settings);
+}
+
+// sets the unit of angles for trigonometric functions, scripts can change it with 'mode deg'
+pub fn set_angle_mode(mode: AngleMode) {
+    let mut settings = session::settings();
+    settings.angle_mode = mode;
+    session::set_settings(settings);
+}
+
+// makes 'i' the imaginary unit and lets functions like sqrt or ln return complex numbers
+pub fn set_complex_numbers(enabled: bool) {
+    let mut settings = session::settings();
+    settings.complex_numbers = enabled;
+    session::set_settings(settings);
+}
+
+// in the exact mode, '1/3' stays a fraction instead of being rounded to a decimal number,
+// NumberMode::Precise(50) calculates with 50 significant digits like 'precision 50',
+// NumberMode::Float with the faster f64 numbers like 'mode float',
+// NumberMode::Integer(IntegerType::U8) with 8 bit unsigned integers like 'mode u8'
+pub fn set_number_mode(mode: NumberMode) {
+    let mut settings = session::settings();
+    settings.number_mode = mode;
+    session::set_settings(settings);
+}
+
+// whether integers which don't fit into the integer type wrap around like 'mode wrap',
+// or are an error like 'mode checked'
+pub fn set_overflow(overflow: Overflow) {
+    let mut settings = session::settings();
+    settings.overflow = overflow;
+    session::set_settings(settings);
+}
+
+// the base in which integers of the integer mode are printed, like 'mode hex'
+pub fn set_radix(radix: Radix) {
+    let mut settings = session::settings();
+    settings.radix = radix;
+    session::set_settings(settings);
+}
+
+// scripts are run on their own thread, so deep recursion doesn't depend on the caller's stack
 pub fn process(input: &str) -> String {
+    std::thread::scope(|scope| {
+        std::thread::Builder::new()
+            .stack_size(STACK_SIZE)
+            .spawn_scoped(scope, || run(input))
+            .expect("Failed to spawn the interpreter thread")
+            .join()
+            .unwrap_or_else(|e| std::panic::resume_unwind(e))
+    })
+}
+
+fn run(input: &str) -> String {
     let mut output_stream = String::new();
     let parse_result = parser::parse(input);
     match parse_result {
@@ -38,6 +134,9 @@ pub fn process(input: &str) -> String {
                         instruction::ReturnValue::Return(value) => {
                             return format_value(value);
                         }
+                        instruction::ReturnValue::TailCall(_) => {
+                            unreachable!("Tail calls are only made in functions")
+                        }
                         instruction::ReturnValue::None => (),
                         instruction::ReturnValue::Break => {
                             return match result {
@@ -82,27 +181,47 @@ pub fn process(input: &str) -> String {
     }
 }
 
-fn format_value(number: Decimal) -> String {
-    number.round_dp(6).normalize().to_string()
-}
-
-#[allow(dead_code)]
-fn format_float(number: f64) -> String {
-    let mut string = format!("{:.6}", number);
-    while string.ends_with('0') {
-        string.pop();
-    }
-    if string.ends_with('.') {
-        string.pop();
+// exact fractions, precise numbers and strings are printed as they are, everything else
+// is rounded, including the elements of lists, where strings are quoted
+fn format_value(value: Value) -> String {
+    match value {
+        // like the tiny physical constants outside of the precise mode
+        Value::Precise(x) if session::settings().number_mode.precision().is_none() => {
+            precise::format_rounded(&x)
+        }
+        Value::Rational(_) | Value::Precise(_) | Value::Integer(_) | Value::Function(_) => {
+            value.to_string()
+        }
+        Value::Float(x) => x.format(),
+        Value::List(elements) => {
+            let elements: Vec<String> = elements
+                .into_iter()
+                .map(|element| match element {
+                    Value::String(string) => format!("{string:?}"),
+                    _ => format_value(element),
+                })
+                .collect();
+            format!("[{}]", elements.join(", "))
+        }
+        Value::String(string) => string,
+        _ => {
+            let round = |number: Decimal| number.round_dp(6).normalize();
+            Value::complex(round(value.re()), round(value.im())).to_string()
+        }
     }
-    string
 }
 
 use std::ffi::{CStr, CString};
 use std::os::raw::c_char;
 
+/// Runs a script and returns its output as a new string.
+///
+/// # Safety
+///
+/// `input` must be a valid pointer to a nul-terminated UTF-8 string, which stays
+/// unchanged during the call. The result has to be released with `fncalc_free`.
 #[no_mangle]
-pub extern "C" fn fncalc_process(input: *const c_char) -> *mut c_char {
+pub unsafe extern "C" fn fncalc_process(input: *const c_char) -> *mut c_char {
     let cstr = unsafe { CStr::from_ptr(input) };
     CString::new(process(cstr.to_str().unwrap()))
         .unwrap()
@@ -114,8 +233,13 @@ pub extern "C" fn fncalc_reset() {
     session::clear();
 }
 
+/// Releases a string returned by `fncalc_process`.
+///
+/// # Safety
+///
+/// `input` must have been returned by `fncalc_process` and not been freed yet.
 #[no_mangle]
-pub extern "C" fn fncalc_free(input: *mut c_char) {
+pub unsafe extern "C" fn fncalc_free(input: *mut c_char) {
     unsafe {
         let _ = CString::from_raw(input);
     }
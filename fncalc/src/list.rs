@@ -0,0 +1,219 @@
+use crate::expression::Expression;
+use crate::instruction::{self, ErrorKind};
+use crate::session;
+use crate::value::Value;
+use crate::LOOP_LIMIT;
+
+#[derive(Debug, Clone)]
+pub struct ListLiteral {
+    context: String,
+    elements: Vec<Expression>,
+}
+
+impl std::fmt::Display for ListLiteral {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.context)
+    }
+}
+
+impl ListLiteral {
+    pub fn new(context: String, elements: Vec<Expression>) -> ListLiteral {
+        ListLiteral { context, elements }
+    }
+
+    pub fn exec(&self, output_stream: &mut String) -> Result<Value, instruction::Error> {
+        let mut elements = Vec::new();
+        for element in &self.elements {
+            elements.push(
+                element
+                    .exec(output_stream)?
+                    .expect("Expressions should always return a value on success"),
+            );
+        }
+        Ok(Value::List(elements))
+    }
+}
+
+// '[x^2 for x in xs if x > 0]', the variable is set in the current namespace like in a for loop
+#[derive(Debug, Clone)]
+pub struct Comprehension {
+    context: String,
+    element: Expression,
+    variable: String,
+    iterable: Expression,
+    condition: Option<Expression>,
+}
+
+impl std::fmt::Display for Comprehension {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.context)
+    }
+}
+
+impl Comprehension {
+    pub fn new(
+        context: String,
+        element: Expression,
+        variable: String,
+        iterable: Expression,
+        condition: Option<Expression>,
+    ) -> Comprehension {
+        Comprehension {
+            context,
+            element,
+            variable,
+            iterable,
+            condition,
+        }
+    }
+
+    pub fn exec(
+        &self,
+        context: &str,
+        pos: usize,
+        output_stream: &mut String,
+    ) -> Result<Value, instruction::Error> {
+        let iterable = self
+            .iterable
+            .exec(output_stream)?
+            .expect("Expressions should always return a value on success");
+
+        // the variable only exists inside the comprehension, one with the same name
+        // outside of it keeps its value
+        let shadowed = session::get_variable(&self.variable);
+        let result = self.elements(iterable, context, pos, output_stream);
+        match shadowed {
+            Some(value) => session::set_variable(&self.variable, value),
+            None => session::remove_variable(&self.variable),
+        }
+        result
+    }
+
+    fn elements(
+        &self,
+        iterable: Value,
+        context: &str,
+        pos: usize,
+        output_stream: &mut String,
+    ) -> Result<Value, instruction::Error> {
+        let mut elements = Vec::new();
+        for value in items(iterable, context, pos)? {
+            session::set_variable(&self.variable, value);
+            if let Some(condition) = &self.condition {
+                let condition_result = condition
+                    .exec(output_stream)?
+                    .expect("Expressions should always return a value on success");
+                if condition_result.is_zero() {
+                    continue;
+                }
+            }
+            elements.push(
+                self.element
+                    .exec(output_stream)?
+                    .expect("Expressions should always return a value on success"),
+            );
+        }
+        Ok(Value::List(elements))
+    }
+}
+
+// the elements a for loop or a comprehension iterates over, at most LOOP_LIMIT of them
+pub fn items(iterable: Value, context: &str, pos: usize) -> Result<Vec<Value>, instruction::Error> {
+    match iterable {
+        Value::List(elements) if elements.len() > LOOP_LIMIT => Err(instruction::Error::new(
+            String::new(),
+            0,
+            ErrorKind::IterationLimitReached,
+        )),
+        Value::List(elements) => Ok(elements),
+        _ => Err(error(context, pos, ErrorKind::TypeError)),
+    }
+}
+
+// 'xs[i]' or 'xs[start:end]' after a list, where negative indices count from the end.
+// A slice leaves out the element at its end, and its missing bounds are the ends of the list.
+#[derive(Debug, Clone)]
+pub enum Index {
+    Element(Expression),
+    Slice(Option<Expression>, Option<Expression>),
+}
+
+impl Index {
+    pub fn apply(
+        &self,
+        list: &Value,
+        context: &str,
+        pos: usize,
+        output_stream: &mut String,
+    ) -> Result<Value, instruction::Error> {
+        let elements = match list {
+            Value::List(elements) => elements,
+            _ => return Err(error(context, pos, ErrorKind::TypeError)),
+        };
+
+        match self {
+            Index::Element(index) => {
+                let index = position(index, elements.len(), context, pos, output_stream)?;
+                match usize::try_from(index) {
+                    Ok(index) if index < elements.len() => Ok(elements[index].clone()),
+                    _ => Err(error(context, pos, ErrorKind::IndexOutOfRange)),
+                }
+            }
+            Index::Slice(start, end) => {
+                // slices are cut off at the ends of the list instead of being out of range
+                let mut bound = |index: &Option<Expression>, default: usize| match index {
+                    Some(index) => Ok(
+                        position(index, elements.len(), context, pos, output_stream)?
+                            .clamp(0, elements.len() as i128) as usize,
+                    ),
+                    None => Ok(default),
+                };
+                let start = bound(start, 0)?;
+                let end = bound(end, elements.len())?;
+                Ok(Value::List(elements[start..end.max(start)].to_vec()))
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for Index {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Index::Element(index) => write!(f, "[{index}]"),
+            Index::Slice(start, end) => {
+                let bound = |index: &Option<Expression>| match index {
+                    Some(index) => index.to_string(),
+                    None => String::new(),
+                };
+                write!(f, "[{}:{}]", bound(start), bound(end))
+            }
+        }
+    }
+}
+
+// the position of an index in a list of the given length, which may be outside of the list
+fn position(
+    index: &Expression,
+    len: usize,
+    context: &str,
+    pos: usize,
+    output_stream: &mut String,
+) -> Result<i128, instruction::Error> {
+    let index = index
+        .exec(output_stream)?
+        .expect("Expressions should always return a value on success");
+    index.expect_integer(context, pos)?;
+    // far beyond the end of any list if it doesn't even fit
+    let index = index
+        .to_integer(context, pos)
+        .map_err(|_| error(context, pos, ErrorKind::IndexOutOfRange))?;
+    if index < 0 {
+        Ok(index + len as i128)
+    } else {
+        Ok(index)
+    }
+}
+
+fn error(context: &str, pos: usize, kind: ErrorKind) -> instruction::Error {
+    instruction::Error::new(context.to_string(), pos, kind)
+}
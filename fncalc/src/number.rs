@@ -0,0 +1,791 @@
+use crate::instruction;
+use crate::value::Value;
+use rust_decimal::prelude::*;
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::Neg;
+
+// the real numbers the interpreter calculates with. Decimal numbers are exact decimal
+// fractions and report an overflow or a result outside a function's domain as an error,
+// f64 is much faster and follows IEEE 754, so '1/0' is inf and 'sqrt(-1)' is NaN
+pub trait Number: Copy + PartialOrd + fmt::Display + Neg<Output = Self> {
+    fn from_integer(n: i64) -> Self;
+
+    // a value holds a number of each kind in its own variant
+    fn into_value(self) -> Value;
+    fn from_value(value: &Value, context: &str, pos: usize) -> Result<Self, instruction::Error>;
+
+    fn math_add(&self, rhs: Self, context: &str, pos: usize) -> Result<Self, instruction::Error>;
+    fn math_mul(&self, rhs: Self, context: &str, pos: usize) -> Result<Self, instruction::Error>;
+    fn math_div(&self, rhs: Self, context: &str, pos: usize) -> Result<Self, instruction::Error>;
+    fn math_rem(&self, rhs: Self, context: &str, pos: usize) -> Result<Self, instruction::Error>;
+
+    fn compare(&self, rhs: &Self) -> Ordering;
+
+    // rounded to six decimal places, the way results are printed
+    fn format(&self) -> String;
+
+    fn to_radians(&self) -> Self;
+    fn to_degrees(&self) -> Self;
+
+    fn trig_sin(&self, context: &str, pos: usize) -> Result<Self, instruction::Error>;
+    fn trig_cos(&self, context: &str, pos: usize) -> Result<Self, instruction::Error>;
+    fn trig_tan(&self, context: &str, pos: usize) -> Result<Self, instruction::Error>;
+    fn trig_tan_deg(&self, context: &str, pos: usize) -> Result<Self, instruction::Error>;
+
+    fn trig_asin(&self, context: &str, pos: usize) -> Result<Self, instruction::Error>;
+    fn trig_acos(&self, context: &str, pos: usize) -> Result<Self, instruction::Error>;
+    fn trig_atan(&self, context: &str, pos: usize) -> Result<Self, instruction::Error>;
+
+    fn math_ln(&self, context: &str, pos: usize) -> Result<Self, instruction::Error>;
+    fn math_log(&self, context: &str, pos: usize) -> Result<Self, instruction::Error>;
+    fn math_pow(&self, rhs: Self, context: &str, pos: usize) -> Result<Self, instruction::Error>;
+
+    fn math_sqrt(&self, context: &str, pos: usize) -> Result<Self, instruction::Error>;
+    fn math_cbrt(&self, context: &str, pos: usize) -> Result<Self, instruction::Error>;
+    fn math_exp(&self, context: &str, pos: usize) -> Result<Self, instruction::Error>;
+    fn math_log_base(
+        &self,
+        base: Self,
+        context: &str,
+        pos: usize,
+    ) -> Result<Self, instruction::Error>;
+    fn math_round(&self, dp: Self, context: &str, pos: usize) -> Result<Self, instruction::Error>;
+    fn math_hypot(&self, rhs: Self, context: &str, pos: usize) -> Result<Self, instruction::Error>;
+    fn trig_atan2(&self, x: Self, context: &str, pos: usize) -> Result<Self, instruction::Error>;
+
+    fn hyp_sinh(&self, context: &str, pos: usize) -> Result<Self, instruction::Error>;
+    fn hyp_cosh(&self, context: &str, pos: usize) -> Result<Self, instruction::Error>;
+    fn hyp_tanh(&self, context: &str, pos: usize) -> Result<Self, instruction::Error>;
+    fn hyp_asinh(&self, context: &str, pos: usize) -> Result<Self, instruction::Error>;
+    fn hyp_acosh(&self, context: &str, pos: usize) -> Result<Self, instruction::Error>;
+    fn hyp_atanh(&self, context: &str, pos: usize) -> Result<Self, instruction::Error>;
+}
+
+impl Number for Decimal {
+    fn from_integer(n: i64) -> Decimal {
+        Decimal::from(n)
+    }
+
+    fn into_value(self) -> Value {
+        Value::Real(self)
+    }
+
+    fn from_value(value: &Value, context: &str, pos: usize) -> Result<Decimal, instruction::Error> {
+        value.real(context, pos)
+    }
+
+    fn math_add(
+        &self,
+        rhs: Decimal,
+        context: &str,
+        pos: usize,
+    ) -> Result<Decimal, instruction::Error> {
+        out_of_range(self.checked_add(rhs), context, pos)
+    }
+
+    fn math_mul(
+        &self,
+        rhs: Decimal,
+        context: &str,
+        pos: usize,
+    ) -> Result<Decimal, instruction::Error> {
+        out_of_range(self.checked_mul(rhs), context, pos)
+    }
+
+    fn math_div(
+        &self,
+        rhs: Decimal,
+        context: &str,
+        pos: usize,
+    ) -> Result<Decimal, instruction::Error> {
+        if rhs.is_zero() {
+            return Err(instruction::Error::new(
+                context.to_string(),
+                pos,
+                instruction::ErrorKind::ZeroDivision,
+            ));
+        }
+        out_of_range(self.checked_div(rhs), context, pos)
+    }
+
+    fn math_rem(
+        &self,
+        rhs: Decimal,
+        context: &str,
+        pos: usize,
+    ) -> Result<Decimal, instruction::Error> {
+        if rhs.is_zero() {
+            return Err(instruction::Error::new(
+                context.to_string(),
+                pos,
+                instruction::ErrorKind::ZeroDivision,
+            ));
+        }
+        out_of_range(self.checked_rem(rhs), context, pos)
+    }
+
+    fn compare(&self, rhs: &Decimal) -> Ordering {
+        self.cmp(rhs)
+    }
+
+    fn format(&self) -> String {
+        self.round_dp(6).normalize().to_string()
+    }
+
+    fn to_radians(&self) -> Decimal {
+        self * (Decimal::PI / Decimal::from_isize(180).unwrap())
+    }
+
+    fn to_degrees(&self) -> Decimal {
+        self * (Decimal::from_isize(180).unwrap() / Decimal::PI)
+    }
+
+    fn trig_sin(&self, context: &str, pos: usize) -> Result<Decimal, instruction::Error> {
+        Ok(checked(sin_cos(*self), context, pos)?.0)
+    }
+
+    fn trig_cos(&self, context: &str, pos: usize) -> Result<Decimal, instruction::Error> {
+        Ok(checked(sin_cos(*self), context, pos)?.1)
+    }
+
+    fn trig_tan(&self, context: &str, pos: usize) -> Result<Decimal, instruction::Error> {
+        let (sin, cos) = checked(sin_cos(*self), context, pos)?;
+        // at the odd multiples of pi/2, only the rounding error of the angle is left in the cosine
+        if cos.abs() <= Decimal::new(1, 27) * self.abs().max(Decimal::ONE) {
+            return Err(instruction::Error::new(
+                context.to_string(),
+                pos,
+                instruction::ErrorKind::DomainError,
+            ));
+        }
+
+        checked(sin.checked_div(cos), context, pos)
+    }
+
+    fn trig_tan_deg(&self, context: &str, pos: usize) -> Result<Decimal, instruction::Error> {
+        if self.abs() % Decimal::from_isize(180).unwrap() == Decimal::from_isize(90).unwrap() {
+            return Err(instruction::Error::new(
+                context.to_string(),
+                pos,
+                instruction::ErrorKind::DomainError,
+            ));
+        }
+
+        let (sin, cos) = checked(sin_cos(self.to_radians()), context, pos)?;
+        checked(sin.checked_div(cos), context, pos)
+    }
+
+    fn trig_asin(&self, context: &str, pos: usize) -> Result<Decimal, instruction::Error> {
+        if self.abs() > Decimal::ONE {
+            return Err(instruction::Error::new(
+                context.to_string(),
+                pos,
+                instruction::ErrorKind::DomainError,
+            ));
+        }
+
+        // asin(x) = 2 * atan(x / (1 + sqrt(1 - x^2))), which stays accurate near -1 and 1
+        let root = checked(
+            ((Decimal::ONE - self) * (Decimal::ONE + self)).sqrt(),
+            context,
+            pos,
+        )?;
+        Ok(Decimal::TWO * atan(self / (Decimal::ONE + root)))
+    }
+
+    fn trig_acos(&self, context: &str, pos: usize) -> Result<Decimal, instruction::Error> {
+        if self.abs() > Decimal::ONE {
+            return Err(instruction::Error::new(
+                context.to_string(),
+                pos,
+                instruction::ErrorKind::DomainError,
+            ));
+        }
+        if *self == Decimal::NEGATIVE_ONE {
+            return Ok(Decimal::PI);
+        }
+
+        // acos(x) = 2 * atan(sqrt((1 - x) / (1 + x))), which stays accurate near 1
+        let root = checked(
+            ((Decimal::ONE - self) / (Decimal::ONE + self)).sqrt(),
+            context,
+            pos,
+        )?;
+        Ok(Decimal::TWO * atan(root))
+    }
+
+    fn trig_atan(&self, _context: &str, _pos: usize) -> Result<Decimal, instruction::Error> {
+        Ok(atan(*self))
+    }
+
+    fn math_ln(&self, context: &str, pos: usize) -> Result<Decimal, instruction::Error> {
+        match self.checked_ln() {
+            Some(value) => Ok(value),
+            None => Err(instruction::Error::new(
+                context.to_string(),
+                pos,
+                instruction::ErrorKind::DomainError,
+            )),
+        }
+    }
+
+    fn math_log(&self, context: &str, pos: usize) -> Result<Decimal, instruction::Error> {
+        match self.checked_log10() {
+            Some(value) => Ok(value),
+            None => Err(instruction::Error::new(
+                context.to_string(),
+                pos,
+                instruction::ErrorKind::DomainError,
+            )),
+        }
+    }
+
+    fn math_pow(
+        &self,
+        rhs: Decimal,
+        context: &str,
+        pos: usize,
+    ) -> Result<Decimal, instruction::Error> {
+        // checked_powd gives 0 for these
+        if self.is_zero() && rhs < Decimal::ZERO {
+            return Err(instruction::Error::new(
+                context.to_string(),
+                pos,
+                instruction::ErrorKind::ZeroDivision,
+            ));
+        }
+        match self.checked_powd(rhs) {
+            Some(value) => Ok(value),
+            None => Err(instruction::Error::new(
+                context.to_string(),
+                pos,
+                instruction::ErrorKind::InvalidExponent,
+            )),
+        }
+    }
+
+    fn math_sqrt(&self, context: &str, pos: usize) -> Result<Decimal, instruction::Error> {
+        if *self < Decimal::ZERO {
+            return Err(instruction::Error::new(
+                context.to_string(),
+                pos,
+                instruction::ErrorKind::DomainError,
+            ));
+        }
+        checked(self.sqrt(), context, pos)
+    }
+
+    fn math_cbrt(&self, context: &str, pos: usize) -> Result<Decimal, instruction::Error> {
+        if self.is_zero() {
+            return Ok(Decimal::ZERO);
+        }
+
+        // Newton's method starting from the floating point estimate
+        let value = self.abs();
+        let mut root = checked(
+            Decimal::from_f64(value.to_f64().unwrap().cbrt()),
+            context,
+            pos,
+        )?;
+        for _ in 0..100 {
+            let square = checked(root.checked_mul(root), context, pos)?;
+            let next = root - (root * square - value) / (Decimal::from(3) * square);
+            if next == root {
+                break;
+            }
+            root = next;
+        }
+
+        if self.is_sign_negative() {
+            Ok(-root)
+        } else {
+            Ok(root)
+        }
+    }
+
+    fn math_exp(&self, context: &str, pos: usize) -> Result<Decimal, instruction::Error> {
+        if self.is_sign_negative() {
+            // too small to be represented
+            return match self.abs().math_exp(context, pos) {
+                Ok(value) => Ok(Decimal::ONE / value),
+                Err(_) => Ok(Decimal::ZERO),
+            };
+        }
+
+        // the series only converges quickly for small values, so e^n is split off
+        let integer = self.trunc();
+        let fraction = checked((self - integer).checked_exp(), context, pos)?;
+        let integer = checked(integer.to_i64(), context, pos)?;
+        let power = checked(Decimal::E.checked_powi(integer), context, pos)?;
+        checked(power.checked_mul(fraction), context, pos)
+    }
+
+    fn math_log_base(
+        &self,
+        base: Decimal,
+        context: &str,
+        pos: usize,
+    ) -> Result<Decimal, instruction::Error> {
+        if base == Decimal::ONE {
+            return Err(instruction::Error::new(
+                context.to_string(),
+                pos,
+                instruction::ErrorKind::DomainError,
+            ));
+        }
+
+        checked(
+            self.math_ln(context, pos)?
+                .checked_div(base.math_ln(context, pos)?),
+            context,
+            pos,
+        )
+    }
+
+    fn math_round(
+        &self,
+        dp: Decimal,
+        context: &str,
+        pos: usize,
+    ) -> Result<Decimal, instruction::Error> {
+        if !dp.fract().is_zero() {
+            return Err(instruction::Error::new(
+                context.to_string(),
+                pos,
+                instruction::ErrorKind::DomainError,
+            ));
+        }
+
+        match dp.to_u32() {
+            Some(dp) if dp <= 28 => {
+                Ok(self.round_dp_with_strategy(dp, RoundingStrategy::MidpointAwayFromZero))
+            }
+            _ => Err(instruction::Error::new(
+                context.to_string(),
+                pos,
+                instruction::ErrorKind::DomainError,
+            )),
+        }
+    }
+
+    fn math_hypot(
+        &self,
+        rhs: Decimal,
+        context: &str,
+        pos: usize,
+    ) -> Result<Decimal, instruction::Error> {
+        // scaled by the larger value, so the squares can't overflow
+        let (big, small) = if self.abs() > rhs.abs() {
+            (self.abs(), rhs.abs())
+        } else {
+            (rhs.abs(), self.abs())
+        };
+        if big.is_zero() {
+            return Ok(Decimal::ZERO);
+        }
+
+        let ratio = small / big;
+        let root = (Decimal::ONE + ratio * ratio).math_sqrt(context, pos)?;
+        checked(big.checked_mul(root), context, pos)
+    }
+
+    fn trig_atan2(
+        &self,
+        x: Decimal,
+        context: &str,
+        pos: usize,
+    ) -> Result<Decimal, instruction::Error> {
+        let y = *self;
+        if x.is_zero() {
+            return Ok(match y.cmp(&Decimal::ZERO) {
+                Ordering::Greater => Decimal::HALF_PI,
+                Ordering::Less => -Decimal::HALF_PI,
+                Ordering::Equal => Decimal::ZERO,
+            });
+        }
+
+        let angle = checked(y.checked_div(x), context, pos)?.trig_atan(context, pos)?;
+        if x.is_sign_positive() {
+            Ok(angle)
+        } else if y.is_sign_negative() {
+            Ok(angle - Decimal::PI)
+        } else {
+            Ok(angle + Decimal::PI)
+        }
+    }
+
+    fn hyp_sinh(&self, context: &str, pos: usize) -> Result<Decimal, instruction::Error> {
+        let exp = self.math_exp(context, pos)?;
+        let exp_neg = (-self).math_exp(context, pos)?;
+        Ok((exp - exp_neg) / Decimal::TWO)
+    }
+
+    fn hyp_cosh(&self, context: &str, pos: usize) -> Result<Decimal, instruction::Error> {
+        let exp = self.math_exp(context, pos)?;
+        let exp_neg = (-self).math_exp(context, pos)?;
+        Ok((exp + exp_neg) / Decimal::TWO)
+    }
+
+    fn hyp_tanh(&self, context: &str, pos: usize) -> Result<Decimal, instruction::Error> {
+        // equal to 1 within the precision of Decimal, and e^2x would overflow from about 33.3
+        if self.abs() > Decimal::from(33) {
+            return Ok(self.signum());
+        }
+
+        let exp = (self * Decimal::TWO).math_exp(context, pos)?;
+        Ok((exp - Decimal::ONE) / (exp + Decimal::ONE))
+    }
+
+    fn hyp_asinh(&self, context: &str, pos: usize) -> Result<Decimal, instruction::Error> {
+        let value = self.abs();
+        let result = if value > large_hyp_argument() {
+            ln_twice(value, context, pos)?
+        } else {
+            let square = checked(value.checked_mul(value), context, pos)?;
+            (value + (square + Decimal::ONE).math_sqrt(context, pos)?).math_ln(context, pos)?
+        };
+        if self.is_sign_negative() {
+            Ok(-result)
+        } else {
+            Ok(result)
+        }
+    }
+
+    fn hyp_acosh(&self, context: &str, pos: usize) -> Result<Decimal, instruction::Error> {
+        if *self < Decimal::ONE {
+            return Err(instruction::Error::new(
+                context.to_string(),
+                pos,
+                instruction::ErrorKind::DomainError,
+            ));
+        }
+
+        if *self > large_hyp_argument() {
+            return ln_twice(*self, context, pos);
+        }
+
+        let square = checked(self.checked_mul(*self), context, pos)?;
+        (self + (square - Decimal::ONE).math_sqrt(context, pos)?).math_ln(context, pos)
+    }
+
+    fn hyp_atanh(&self, context: &str, pos: usize) -> Result<Decimal, instruction::Error> {
+        if self.abs() >= Decimal::ONE {
+            return Err(instruction::Error::new(
+                context.to_string(),
+                pos,
+                instruction::ErrorKind::DomainError,
+            ));
+        }
+
+        Ok(((Decimal::ONE + self) / (Decimal::ONE - self)).math_ln(context, pos)? / Decimal::TWO)
+    }
+}
+
+// none of the functions fail, results outside the domain are NaN and overflows are infinite
+impl Number for f64 {
+    fn from_integer(n: i64) -> f64 {
+        n as f64
+    }
+
+    fn into_value(self) -> Value {
+        Value::Float(self)
+    }
+
+    fn from_value(value: &Value, context: &str, pos: usize) -> Result<f64, instruction::Error> {
+        value.float(context, pos)
+    }
+
+    fn math_add(&self, rhs: f64, _context: &str, _pos: usize) -> Result<f64, instruction::Error> {
+        Ok(self + rhs)
+    }
+
+    fn math_mul(&self, rhs: f64, _context: &str, _pos: usize) -> Result<f64, instruction::Error> {
+        Ok(self * rhs)
+    }
+
+    fn math_div(&self, rhs: f64, _context: &str, _pos: usize) -> Result<f64, instruction::Error> {
+        Ok(self / rhs)
+    }
+
+    fn math_rem(&self, rhs: f64, _context: &str, _pos: usize) -> Result<f64, instruction::Error> {
+        Ok(self % rhs)
+    }
+
+    // NaN is neither less nor greater than anything
+    fn compare(&self, rhs: &f64) -> Ordering {
+        self.partial_cmp(rhs).unwrap_or(Ordering::Equal)
+    }
+
+    // large numbers are written in scientific notation like '1e300' instead of with all digits
+    fn format(&self) -> String {
+        if self.abs() >= 1e16 && self.is_finite() {
+            return format!("{:e}", self);
+        }
+
+        let mut string = format!("{:.6}", self);
+        while string.ends_with('0') {
+            string.pop();
+        }
+        if string.ends_with('.') {
+            string.pop();
+        }
+        if string == "-0" {
+            string.remove(0);
+        }
+        string
+    }
+
+    fn to_radians(&self) -> f64 {
+        f64::to_radians(*self)
+    }
+
+    fn to_degrees(&self) -> f64 {
+        f64::to_degrees(*self)
+    }
+
+    fn trig_sin(&self, _context: &str, _pos: usize) -> Result<f64, instruction::Error> {
+        Ok(self.sin())
+    }
+
+    fn trig_cos(&self, _context: &str, _pos: usize) -> Result<f64, instruction::Error> {
+        Ok(self.cos())
+    }
+
+    fn trig_tan(&self, _context: &str, _pos: usize) -> Result<f64, instruction::Error> {
+        Ok(self.tan())
+    }
+
+    fn trig_tan_deg(&self, _context: &str, _pos: usize) -> Result<f64, instruction::Error> {
+        Ok(f64::to_radians(*self).tan())
+    }
+
+    fn trig_asin(&self, _context: &str, _pos: usize) -> Result<f64, instruction::Error> {
+        Ok(self.asin())
+    }
+
+    fn trig_acos(&self, _context: &str, _pos: usize) -> Result<f64, instruction::Error> {
+        Ok(self.acos())
+    }
+
+    fn trig_atan(&self, _context: &str, _pos: usize) -> Result<f64, instruction::Error> {
+        Ok(self.atan())
+    }
+
+    fn math_ln(&self, _context: &str, _pos: usize) -> Result<f64, instruction::Error> {
+        Ok(self.ln())
+    }
+
+    fn math_log(&self, _context: &str, _pos: usize) -> Result<f64, instruction::Error> {
+        Ok(self.log10())
+    }
+
+    fn math_pow(&self, rhs: f64, _context: &str, _pos: usize) -> Result<f64, instruction::Error> {
+        Ok(self.powf(rhs))
+    }
+
+    fn math_sqrt(&self, _context: &str, _pos: usize) -> Result<f64, instruction::Error> {
+        Ok(self.sqrt())
+    }
+
+    fn math_cbrt(&self, _context: &str, _pos: usize) -> Result<f64, instruction::Error> {
+        Ok(self.cbrt())
+    }
+
+    fn math_exp(&self, _context: &str, _pos: usize) -> Result<f64, instruction::Error> {
+        Ok(self.exp())
+    }
+
+    fn math_log_base(
+        &self,
+        base: f64,
+        _context: &str,
+        _pos: usize,
+    ) -> Result<f64, instruction::Error> {
+        Ok(self.log(base))
+    }
+
+    // the number of decimal places is checked like for Decimal numbers
+    fn math_round(&self, dp: f64, context: &str, pos: usize) -> Result<f64, instruction::Error> {
+        if dp.fract() != 0.0 || !(0.0..=28.0).contains(&dp) {
+            return Err(instruction::Error::new(
+                context.to_string(),
+                pos,
+                instruction::ErrorKind::DomainError,
+            ));
+        }
+
+        // rounding half away from zero, numbers too large for the scaling have no decimal places
+        let factor = 10f64.powi(dp as i32);
+        let scaled = self * factor;
+        if scaled.is_finite() {
+            Ok(scaled.round() / factor)
+        } else {
+            Ok(*self)
+        }
+    }
+
+    fn math_hypot(&self, rhs: f64, _context: &str, _pos: usize) -> Result<f64, instruction::Error> {
+        Ok(self.hypot(rhs))
+    }
+
+    fn trig_atan2(&self, x: f64, _context: &str, _pos: usize) -> Result<f64, instruction::Error> {
+        Ok(self.atan2(x))
+    }
+
+    fn hyp_sinh(&self, _context: &str, _pos: usize) -> Result<f64, instruction::Error> {
+        Ok(self.sinh())
+    }
+
+    fn hyp_cosh(&self, _context: &str, _pos: usize) -> Result<f64, instruction::Error> {
+        Ok(self.cosh())
+    }
+
+    fn hyp_tanh(&self, _context: &str, _pos: usize) -> Result<f64, instruction::Error> {
+        Ok(self.tanh())
+    }
+
+    fn hyp_asinh(&self, _context: &str, _pos: usize) -> Result<f64, instruction::Error> {
+        Ok(self.asinh())
+    }
+
+    fn hyp_acosh(&self, _context: &str, _pos: usize) -> Result<f64, instruction::Error> {
+        Ok(self.acosh())
+    }
+
+    fn hyp_atanh(&self, _context: &str, _pos: usize) -> Result<f64, instruction::Error> {
+        Ok(self.atanh())
+    }
+}
+
+// a function of the trait applied to a number, whose result is a value of the same kind,
+// so that the same code handles Decimal and float values
+pub fn unary<N: Number>(
+    x: N,
+    function: fn(&N, &str, usize) -> Result<N, instruction::Error>,
+    context: &str,
+    pos: usize,
+) -> Result<Value, instruction::Error> {
+    Ok(function(&x, context, pos)?.into_value())
+}
+
+pub fn binary<N: Number>(
+    lhs: N,
+    rhs: N,
+    function: fn(&N, N, &str, usize) -> Result<N, instruction::Error>,
+    context: &str,
+    pos: usize,
+) -> Result<Value, instruction::Error> {
+    Ok(function(&lhs, rhs, context, pos)?.into_value())
+}
+
+// a function of real arguments, which are all converted to numbers of the kind N
+pub fn call<N: Number>(
+    function: fn(&[N], &str, usize) -> Result<N, instruction::Error>,
+    arguments: &[Value],
+    context: &str,
+    pos: usize,
+) -> Result<Value, instruction::Error> {
+    let mut numbers = Vec::new();
+    for argument in arguments {
+        numbers.push(N::from_value(argument, context, pos)?);
+    }
+    Ok(function(&numbers, context, pos)?.into_value())
+}
+
+fn checked<T>(value: Option<T>, context: &str, pos: usize) -> Result<T, instruction::Error> {
+    match value {
+        Some(value) => Ok(value),
+        None => Err(instruction::Error::new(
+            context.to_string(),
+            pos,
+            instruction::ErrorKind::MathError,
+        )),
+    }
+}
+
+fn out_of_range<T>(value: Option<T>, context: &str, pos: usize) -> Result<T, instruction::Error> {
+    match value {
+        Some(value) => Ok(value),
+        None => Err(instruction::Error::new(
+            context.to_string(),
+            pos,
+            instruction::ErrorKind::OutOfRange,
+        )),
+    }
+}
+
+// beyond this, x^2 would overflow, while asinh(x) and acosh(x) equal ln(2x)
+// within the precision of Decimal
+fn large_hyp_argument() -> Decimal {
+    Decimal::from(100_000_000_000_000i64)
+}
+
+// ln(2x), without the overflow of 2x
+fn ln_twice(x: Decimal, context: &str, pos: usize) -> Result<Decimal, instruction::Error> {
+    Ok(Decimal::TWO.math_ln(context, pos)? + x.math_ln(context, pos)?)
+}
+
+fn atan(x: Decimal) -> Decimal {
+    if x.is_sign_negative() {
+        return -atan(-x);
+    }
+    if x > Decimal::ONE {
+        return Decimal::HALF_PI - atan(Decimal::ONE / x);
+    }
+
+    // atan(x) = pi/4 + atan((x - 1) / (x + 1)) moves the argument close to zero
+    // without losing precision, after which the Taylor series converges quickly
+    if x > Decimal::new(4142, 4) {
+        return Decimal::QUARTER_PI + atan_series((x - Decimal::ONE) / (x + Decimal::ONE));
+    }
+
+    atan_series(x)
+}
+
+// Taylor series of atan(x) = x - x^3/3 + x^5/5 - ..., meant for small arguments
+fn atan_series(x: Decimal) -> Decimal {
+    let x_squared = x * x;
+    let mut power = x;
+    let mut sum = x;
+    let mut n = Decimal::ONE;
+    loop {
+        power *= -x_squared;
+        n += Decimal::TWO;
+        let term = power / n;
+        if term.is_zero() {
+            return sum;
+        }
+        sum += term;
+    }
+}
+
+// sine and cosine from their Taylor series, after reducing the angle to [-pi/4, pi/4]
+fn sin_cos(angle: Decimal) -> Option<(Decimal, Decimal)> {
+    let quadrant = angle.checked_div(Decimal::HALF_PI)?.round();
+    let x = angle.checked_sub(quadrant.checked_mul(Decimal::HALF_PI)?)?;
+
+    let x_squared = x * x;
+    let mut sin = x;
+    let mut cos = Decimal::ONE;
+    let mut sin_term = x;
+    let mut cos_term = Decimal::ONE;
+    let mut n = Decimal::ZERO;
+    loop {
+        n += Decimal::TWO;
+        cos_term *= -x_squared / (n * (n - Decimal::ONE));
+        sin_term *= -x_squared / (n * (n + Decimal::ONE));
+        if cos_term.is_zero() && sin_term.is_zero() {
+            break;
+        }
+        sin += sin_term;
+        cos += cos_term;
+    }
+
+    match (quadrant % Decimal::from(4)).to_i8()? {
+        0 => Some((sin, cos)),
+        1 | -3 => Some((cos, -sin)),
+        2 | -2 => Some((-sin, -cos)),
+        _ => Some((-cos, sin)),
+    }
+}
@@ -1,28 +1,31 @@
-use crate::branch::Branch;
+use crate::branch::{Branch, Conditional};
+use crate::constant;
 use crate::expression::Expression;
-use crate::function::{Function, FunctionCall, Return};
-use crate::instruction::{Error, ErrorKind, Instruction, Print};
-use crate::session;
+use crate::for_loop::ForLoop;
+use crate::format::{FormatString, Part, Spec};
+use crate::function::{Function, FunctionCall, Parameter, Return};
+use crate::instruction::{Destructuring, Error, ErrorKind, Instruction, Print};
+use crate::list::{Comprehension, Index, ListLiteral};
+use crate::precise;
+use crate::session::{self, Mode, NumberMode};
 use crate::token::{Operator, Parenthesis, Token};
+use crate::value::Value;
 use crate::while_loop::WhileLoop;
-use crate::PI;
+use crate::MAX_PRECISION;
 
 use pest::{iterators::Pair, iterators::Pairs, Parser};
 use pest_derive::Parser;
-use rust_decimal::Decimal;
+use std::sync::Arc;
 
 #[derive(Parser)]
 #[grammar = "grammar.pest"]
 pub struct FnCalcParser;
 
 pub fn parse(input: &str) -> Result<Vec<Instruction>, Error> {
-    let parse_result = FnCalcParser::parse(Rule::start_symbol, &input);
-    let parsed_content;
+    let parse_result = FnCalcParser::parse(Rule::start_symbol, input);
 
-    match parse_result {
-        Ok(mut content) => {
-            parsed_content = content.next().unwrap();
-        }
+    let parsed_content = match parse_result {
+        Ok(mut content) => content.next().unwrap(),
         Err(e) => {
             let pos = match e.line_col {
                 pest::error::LineColLocation::Pos((_, column)) => column,
@@ -35,7 +38,7 @@ pub fn parse(input: &str) -> Result<Vec<Instruction>, Error> {
             );
             return Err(err);
         }
-    }
+    };
 
     let mut result: Vec<Instruction> = Vec::new();
 
@@ -44,8 +47,13 @@ pub fn parse(input: &str) -> Result<Vec<Instruction>, Error> {
             Rule::expression => result.push(Instruction::Expression(build_expression(pair)?)),
             Rule::branch => result.push(Instruction::Branch(build_branch(pair)?)),
             Rule::while_loop => result.push(Instruction::WhileLoop(build_loop(pair)?)),
+            Rule::for_loop => result.push(Instruction::ForLoop(build_for_loop(pair)?)),
             Rule::function_return => result.push(Instruction::Return(build_function_return(pair)?)),
             Rule::print => result.push(Instruction::Print(build_print(pair)?)),
+            Rule::destructuring => {
+                result.push(Instruction::Destructuring(build_destructuring(pair)?))
+            }
+            Rule::mode_setting => result.push(Instruction::Mode(build_mode_setting(pair)?)),
             Rule::function_definition => {
                 let (name, function) = build_function_definition(pair)?;
                 session::add_function(name, function);
@@ -63,21 +71,49 @@ pub fn parse(input: &str) -> Result<Vec<Instruction>, Error> {
 
 fn build_function_definition(function: Pair<Rule>) -> Result<(String, Function), Error> {
     let context = function.as_str().to_string();
+    let offset = function.as_span().start();
 
     let mut function = function.into_inner();
-    let name = function.next().unwrap().as_str().to_string();
+    let name = function.next().unwrap();
+    check_binding(&name, &context, offset)?;
+    let name = name.as_str().to_string();
 
     let mut body: Vec<Instruction> = Vec::new();
-    let mut argument_names: Vec<String> = Vec::new();
+    let mut parameters: Vec<Parameter> = Vec::new();
+    let mut variadic: Option<String> = None;
 
     for pair in function {
         match pair.as_rule() {
-            Rule::identifier => argument_names.push(pair.as_str().to_string()),
+            Rule::parameters => {
+                for parameter in pair.into_inner() {
+                    let rule = parameter.as_rule();
+                    let mut parameter = parameter.into_inner();
+                    let name = parameter.next().unwrap();
+                    check_binding(&name, &context, offset)?;
+                    let name = name.as_str().to_string();
+                    match rule {
+                        Rule::variadic_parameter => variadic = Some(name),
+                        _ => {
+                            let default = parameter.next().map(build_expression).transpose()?;
+                            parameters.push(Parameter::new(name, default));
+                        }
+                    }
+                }
+            }
             Rule::expression => body.push(Instruction::Expression(build_expression(pair)?)),
             Rule::branch => body.push(Instruction::Branch(build_branch(pair)?)),
             Rule::while_loop => body.push(Instruction::WhileLoop(build_loop(pair)?)),
+            Rule::for_loop => body.push(Instruction::ForLoop(build_for_loop(pair)?)),
             Rule::function_return => body.push(Instruction::Return(build_function_return(pair)?)),
             Rule::print => body.push(Instruction::Print(build_print(pair)?)),
+            Rule::destructuring => {
+                body.push(Instruction::Destructuring(build_destructuring(pair)?))
+            }
+            Rule::mode_setting => body.push(Instruction::Mode(build_mode_setting(pair)?)),
+            Rule::function_definition => {
+                let (_, function) = build_function_definition(pair)?;
+                body.push(Instruction::Function(Arc::new(function)));
+            }
             _ => {
                 dbg!(pair.as_rule());
                 unreachable!();
@@ -85,20 +121,102 @@ fn build_function_definition(function: Pair<Rule>) -> Result<(String, Function),
         }
     }
 
-    Ok((name, Function::new(context, body, argument_names)))
+    Ok((
+        name.clone(),
+        Function::new(Some(name), context, body, parameters, variadic),
+    ))
+}
+
+// constants are read-only, so parameters, loop variables and functions can't take their names
+fn check_binding(identifier: &Pair<Rule>, context: &str, offset: usize) -> Result<(), Error> {
+    match constant::get(identifier.as_str()) {
+        Some(_) => Err(Error::new(
+            context.to_string(),
+            identifier.as_span().start() - offset,
+            ErrorKind::InvalidAssignment,
+        )),
+        None => Ok(()),
+    }
 }
 
 fn build_function_return(function_return: Pair<Rule>) -> Result<Return, Error> {
     let context = function_return.as_str().to_string();
-    let expr = function_return.into_inner().next().unwrap();
+    let exprs = function_return
+        .into_inner()
+        .map(build_expression)
+        .collect::<Result<Vec<_>, _>>()?;
 
-    Ok(Return::new(context, build_expression(expr)?))
+    Ok(Return::new(context, exprs))
+}
+
+fn build_destructuring(destructuring: Pair<Rule>) -> Result<Destructuring, Error> {
+    let context = destructuring.as_str().to_string();
+    let offset = destructuring.as_span().start();
+
+    let mut names = Vec::new();
+    let mut exprs = Vec::new();
+    for pair in destructuring.into_inner() {
+        match pair.as_rule() {
+            Rule::identifier => {
+                let pos = pair.as_span().start() - offset;
+                names.push((pair.as_str().to_string(), pos));
+            }
+            _ => exprs.push(build_expression(pair)?),
+        }
+    }
+
+    Ok(Destructuring::new(context, names, exprs))
 }
 
 fn build_print(print: Pair<Rule>) -> Result<Print, Error> {
-    let expr = print.into_inner().next().unwrap();
+    let exprs = print
+        .into_inner()
+        .map(build_expression)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(Print::new(exprs))
+}
 
-    Ok(Print::new(build_expression(expr)?))
+// the text of a string literal with its escape sequences replaced
+fn unescape(text: &str) -> String {
+    let mut result = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some('n') => result.push('\n'),
+                Some('t') => result.push('\t'),
+                Some(escaped) => result.push(escaped),
+                None => (),
+            }
+        } else {
+            result.push(c);
+        }
+    }
+    result
+}
+
+fn build_mode_setting(mode_setting: Pair<Rule>) -> Result<Mode, Error> {
+    let context = mode_setting.as_str().to_string();
+    let offset = mode_setting.as_span().start();
+    let mode = mode_setting.into_inner().nth(1).unwrap();
+    match mode.as_rule() {
+        Rule::angle_mode => Ok(Mode::Angle(mode.as_str().parse().unwrap())),
+        Rule::number_mode => Ok(Mode::Number(mode.as_str().parse().unwrap())),
+        Rule::overflow_mode => Ok(Mode::Overflow(mode.as_str().parse().unwrap())),
+        Rule::radix_mode => Ok(Mode::Radix(mode.as_str().parse().unwrap())),
+        Rule::precision_digits => match mode.as_str().parse() {
+            Ok(digits) if (1..=MAX_PRECISION).contains(&digits) => {
+                Ok(Mode::Number(NumberMode::Precise(digits)))
+            }
+            _ => Err(Error::new(
+                context,
+                mode.as_span().start() - offset,
+                ErrorKind::InvalidPrecision,
+            )),
+        },
+        _ => unreachable!(),
+    }
 }
 
 fn build_branch(control_flow: Pair<Rule>) -> Result<Branch, Error> {
@@ -114,9 +232,14 @@ fn build_branch(control_flow: Pair<Rule>) -> Result<Branch, Error> {
             Rule::expression => body.push(Instruction::Expression(build_expression(pair)?)),
             Rule::branch => body.push(Instruction::Branch(build_branch(pair)?)),
             Rule::while_loop => body.push(Instruction::WhileLoop(build_loop(pair)?)),
+            Rule::for_loop => body.push(Instruction::ForLoop(build_for_loop(pair)?)),
             Rule::loop_break => body.push(Instruction::Break),
             Rule::function_return => body.push(Instruction::Return(build_function_return(pair)?)),
             Rule::print => body.push(Instruction::Print(build_print(pair)?)),
+            Rule::destructuring => {
+                body.push(Instruction::Destructuring(build_destructuring(pair)?))
+            }
+            Rule::mode_setting => body.push(Instruction::Mode(build_mode_setting(pair)?)),
             Rule::branch_else => {
                 body_else = Some(Vec::new());
                 build_body(pair.into_inner(), body_else.as_mut().unwrap())?;
@@ -128,6 +251,144 @@ fn build_branch(control_flow: Pair<Rule>) -> Result<Branch, Error> {
     Ok(Branch::new(context, condition, body, body_else))
 }
 
+fn build_conditional(conditional: Pair<Rule>, pos: usize) -> Result<Token, Error> {
+    let context = conditional.as_str().to_string();
+    let mut conditional = conditional.into_inner();
+
+    let condition = build_expression(conditional.next().unwrap())?;
+    let body = build_expression(conditional.next().unwrap())?;
+    let body_else = build_expression(conditional.next().unwrap())?;
+
+    Ok(Token::new_conditional(
+        pos,
+        Conditional::new(context, condition, body, body_else),
+    ))
+}
+
+fn build_lambda(lambda: Pair<Rule>, pos: usize) -> Result<Token, Error> {
+    // the body takes the semicolon after the lambda
+    let context = lambda.as_str().trim_end_matches(';').trim_end().to_string();
+    let offset = lambda.as_span().start();
+    let mut lambda = lambda.into_inner();
+
+    let mut parameters = Vec::new();
+    for parameter in lambda.next().unwrap().into_inner() {
+        check_binding(&parameter, &context, offset)?;
+        parameters.push(Parameter::new(parameter.as_str().to_string(), None));
+    }
+    let body = build_expression(lambda.next().unwrap())?;
+    let body = vec![Instruction::Expression(body)];
+    let function = Function::new(None, context, body, parameters, None);
+
+    Ok(Token::new_lambda(pos, Arc::new(function)))
+}
+
+fn build_list(list: Pair<Rule>, pos: usize) -> Result<Token, Error> {
+    let context = list.as_str().to_string();
+
+    let mut elements = Vec::new();
+    for pair in list.into_inner() {
+        match pair.as_rule() {
+            Rule::comprehension => return build_comprehension(pair, pos),
+            _ => elements.push(build_expression(pair)?),
+        }
+    }
+
+    Ok(Token::new_list(pos, ListLiteral::new(context, elements)))
+}
+
+fn build_comprehension(comprehension: Pair<Rule>, pos: usize) -> Result<Token, Error> {
+    let context = comprehension.as_str().to_string();
+    let offset = comprehension.as_span().start();
+    let mut comprehension = comprehension.into_inner();
+
+    let element = build_expression(comprehension.next().unwrap())?;
+    let variable = comprehension.next().unwrap();
+    check_binding(&variable, &context, offset)?;
+    let variable = variable.as_str().to_string();
+    let iterable = build_expression(comprehension.next().unwrap())?;
+    let condition = match comprehension.next() {
+        Some(condition) => Some(build_expression(condition)?),
+        None => None,
+    };
+
+    Ok(Token::new_comprehension(
+        pos,
+        Comprehension::new(context, element, variable, iterable, condition),
+    ))
+}
+
+fn build_format_string(
+    format_string: Pair<Rule>,
+    context: &str,
+    offset: usize,
+    pos: usize,
+) -> Result<Token, Error> {
+    let string_context = format_string.as_str().to_string();
+    let start = format_string.as_span().start();
+
+    let mut parts = Vec::new();
+    for part in format_string.into_inner() {
+        match part.as_rule() {
+            Rule::format_text => {
+                let text = unescape(part.as_str())
+                    .replace("{{", "{")
+                    .replace("}}", "}");
+                parts.push(Part::Text(text));
+            }
+            Rule::format_field => {
+                let mut field = part.into_inner();
+                let expression = field.next().unwrap();
+                let field_pos = expression.as_span().start() - start;
+                let expression = build_expression(expression)?;
+                let spec = match field.next() {
+                    Some(spec) => match Spec::parse(&spec.as_str()[1..]) {
+                        Some(parsed) => parsed,
+                        None => {
+                            return Err(Error::new(
+                                context.to_string(),
+                                spec.as_span().start() + 1 - offset,
+                                ErrorKind::InvalidFormat,
+                            ))
+                        }
+                    },
+                    None => Spec::parse("").unwrap(),
+                };
+                parts.push(Part::Field(expression, spec, field_pos));
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    Ok(Token::new_format_string(
+        pos,
+        FormatString::new(string_context, parts),
+    ))
+}
+
+fn build_index(index: Pair<Rule>, pos: usize) -> Result<Token, Error> {
+    let index = index.into_inner().next().unwrap();
+    match index.as_rule() {
+        Rule::expression => Ok(Token::new_index(
+            pos,
+            Index::Element(build_expression(index)?),
+        )),
+        Rule::slice => {
+            let mut bounds = Vec::new();
+            for bound in index.into_inner() {
+                bounds.push(match bound.into_inner().next() {
+                    Some(expression) => Some(build_expression(expression)?),
+                    None => None,
+                });
+            }
+            let end = bounds.pop().unwrap();
+            let start = bounds.pop().unwrap();
+            Ok(Token::new_index(pos, Index::Slice(start, end)))
+        }
+        _ => unreachable!(),
+    }
+}
+
 fn build_loop(control_flow: Pair<Rule>) -> Result<WhileLoop, Error> {
     let context = control_flow.as_str().to_string();
     let mut control_flow = control_flow.into_inner();
@@ -139,15 +400,43 @@ fn build_loop(control_flow: Pair<Rule>) -> Result<WhileLoop, Error> {
     Ok(WhileLoop::new(context, condition, body))
 }
 
+fn build_for_loop(for_loop: Pair<Rule>) -> Result<ForLoop, Error> {
+    let context = for_loop.as_str().to_string();
+    let offset = for_loop.as_span().start();
+    let mut for_loop = for_loop.into_inner();
+    let variable = for_loop.next().unwrap();
+    check_binding(&variable, &context, offset)?;
+    let variable = variable.as_str().to_string();
+    let iterable = for_loop.next().unwrap();
+    let iterable_pos = iterable.as_span().start() - offset;
+    let iterable = build_expression(iterable)?;
+
+    let mut body: Vec<Instruction> = Vec::new();
+    build_body(for_loop, &mut body)?;
+
+    Ok(ForLoop::new(
+        context,
+        variable,
+        iterable,
+        iterable_pos,
+        body,
+    ))
+}
+
 fn build_body(pairs: Pairs<Rule>, output: &mut Vec<Instruction>) -> Result<(), Error> {
     for pair in pairs {
         match pair.as_rule() {
             Rule::expression => output.push(Instruction::Expression(build_expression(pair)?)),
             Rule::branch => output.push(Instruction::Branch(build_branch(pair)?)),
             Rule::while_loop => output.push(Instruction::WhileLoop(build_loop(pair)?)),
+            Rule::for_loop => output.push(Instruction::ForLoop(build_for_loop(pair)?)),
             Rule::loop_break => output.push(Instruction::Break),
             Rule::function_return => output.push(Instruction::Return(build_function_return(pair)?)),
             Rule::print => output.push(Instruction::Print(build_print(pair)?)),
+            Rule::destructuring => {
+                output.push(Instruction::Destructuring(build_destructuring(pair)?))
+            }
+            Rule::mode_setting => output.push(Instruction::Mode(build_mode_setting(pair)?)),
             Rule::branch_else => build_body(pair.into_inner(), output)?,
             _ => unreachable!(),
         }
@@ -160,41 +449,58 @@ fn build_expression(expression: Pair<Rule>) -> Result<Expression, Error> {
     let context = expression.as_str().to_string();
     let offset = expression.as_span().start();
     let mut output = Vec::new();
-    tokenize_expression(expression, offset, &mut output)?;
+    tokenize_expression(expression, &context, offset, &mut output)?;
     Ok(Expression::compile(output, context))
 }
 
 fn tokenize_expression(
     expression: Pair<Rule>,
+    context: &str,
     offset: usize,
     output: &mut Vec<Token>,
 ) -> Result<(), Error> {
     for pair in expression.into_inner() {
         let pos = pair.as_span().start() - offset;
         match pair.as_rule() {
-            Rule::number => output.push(Token::new_val(
-                pos,
-                match Decimal::from_str_radix(pair.as_str(), 10) {
-                    Ok(value) => value,
-                    Err(_) => {
+            Rule::decimal_number | Rule::hex_number | Rule::binary_number | Rule::octal_number => {
+                let value = match parse_number(pair.as_rule(), pair.as_str()) {
+                    Some(value) => value,
+                    None => {
                         return Err(Error::new(
-                            pair.as_str().to_string(),
+                            context.to_string(),
                             pos,
                             ErrorKind::InvalidNumberLiteral,
                         ));
                     }
-                },
-            )),
-            Rule::pi => output.push(Token::new_val(pos, PI)),
+                };
+                match pair.as_rule() {
+                    Rule::decimal_number => output.push(Token::new_val(pos, value)),
+                    _ => output.push(Token::new_bits(pos, value)),
+                }
+            }
+            Rule::string => {
+                let string = pair.as_str();
+                let string = unescape(&string[1..string.len() - 1]);
+                output.push(Token::new_val(pos, Value::String(string)))
+            }
+            Rule::format_string => output.push(build_format_string(pair, context, offset, pos)?),
             Rule::identifier => output.push(Token::new_identifier(pos, pair.as_str().to_string())),
             Rule::add => output.push(Token::new_operator(pos, Operator::Add)),
             Rule::sub => output.push(Token::new_operator(pos, Operator::Sub)),
             Rule::mul => output.push(Token::new_operator(pos, Operator::Mult)),
+            Rule::implicit_mul => output.push(Token::new_operator(pos, Operator::ImplicitMult)),
             Rule::div => output.push(Token::new_operator(pos, Operator::Div)),
+            Rule::int_div => output.push(Token::new_operator(pos, Operator::IntDiv)),
             Rule::modulo => output.push(Token::new_operator(pos, Operator::Mod)),
             Rule::pow => output.push(Token::new_operator(pos, Operator::Pow)),
             Rule::and => output.push(Token::new_operator(pos, Operator::And)),
             Rule::or => output.push(Token::new_operator(pos, Operator::Or)),
+            Rule::xor => output.push(Token::new_operator(pos, Operator::BitXor)),
+            Rule::bit_and => output.push(Token::new_operator(pos, Operator::BitAnd)),
+            Rule::bit_or => output.push(Token::new_operator(pos, Operator::BitOr)),
+            Rule::shift_left => output.push(Token::new_operator(pos, Operator::ShiftLeft)),
+            Rule::shift_right => output.push(Token::new_operator(pos, Operator::ShiftRight)),
+            Rule::range => output.push(Token::new_operator(pos, Operator::Range)),
             Rule::less_than => output.push(Token::new_operator(pos, Operator::LessThan)),
             Rule::greater_than => output.push(Token::new_operator(pos, Operator::GreaterThan)),
             Rule::equal => output.push(Token::new_operator(pos, Operator::Equal)),
@@ -202,30 +508,20 @@ fn tokenize_expression(
             Rule::assign => output.push(Token::new_operator(pos, Operator::Assign)),
             Rule::neg => output.push(Token::new_operator(pos, Operator::Neg)),
             Rule::not => output.push(Token::new_operator(pos, Operator::Not)),
-
-            Rule::sin => output.push(Token::new_operator(pos, Operator::Sin)),
-            Rule::sind => output.push(Token::new_operator(pos, Operator::Sind)),
-            Rule::asin => output.push(Token::new_operator(pos, Operator::Asin)),
-            Rule::asind => output.push(Token::new_operator(pos, Operator::Asind)),
-
-            Rule::cos => output.push(Token::new_operator(pos, Operator::Cos)),
-            Rule::cosd => output.push(Token::new_operator(pos, Operator::Cosd)),
-            Rule::acos => output.push(Token::new_operator(pos, Operator::Acos)),
-            Rule::acosd => output.push(Token::new_operator(pos, Operator::Acosd)),
-
-            Rule::tan => output.push(Token::new_operator(pos, Operator::Tan)),
-            Rule::tand => output.push(Token::new_operator(pos, Operator::Tand)),
-            Rule::atan => output.push(Token::new_operator(pos, Operator::Atan)),
-            Rule::atand => output.push(Token::new_operator(pos, Operator::Atand)),
-
-            Rule::ln => output.push(Token::new_operator(pos, Operator::Ln)),
-            Rule::log => output.push(Token::new_operator(pos, Operator::Log)),
-            Rule::abs => output.push(Token::new_operator(pos, Operator::Abs)),
+            Rule::bit_not => output.push(Token::new_operator(pos, Operator::BitNot)),
 
             Rule::left_par => output.push(Token::new_parenthesis(pos, Parenthesis::Left)),
             Rule::right_par => output.push(Token::new_parenthesis(pos, Parenthesis::Right)),
-            Rule::expression => tokenize_expression(pair, offset, output)?,
-            Rule::function_call => output.push(build_function_call(pair, pos)?),
+            Rule::expression | Rule::term | Rule::factor | Rule::implicit_factor => {
+                tokenize_expression(pair, context, offset, output)?
+            }
+            Rule::function_call | Rule::prefix_call => {
+                output.push(build_function_call(pair, pos)?)
+            }
+            Rule::conditional => output.push(build_conditional(pair, pos)?),
+            Rule::lambda => output.push(build_lambda(pair, pos)?),
+            Rule::list => output.push(build_list(pair, pos)?),
+            Rule::index => output.push(build_index(pair, pos)?),
             Rule::EOI => (),
             _ => {
                 dbg!(pair.as_rule());
@@ -236,6 +532,21 @@ fn tokenize_expression(
     Ok(())
 }
 
+// literals which can't be represented exactly by a Decimal are kept as precise numbers,
+// they are only rounded or rejected when the number mode is known
+fn parse_number(rule: Rule, literal: &str) -> Option<Value> {
+    let literal = literal.replace('_', "");
+    let value = match rule {
+        // skip the radix prefix
+        Rule::hex_number => precise::parse(&literal[2..], 16),
+        Rule::binary_number => precise::parse(&literal[2..], 2),
+        Rule::octal_number => precise::parse(&literal[2..], 8),
+        _ if literal.starts_with('.') => precise::parse(&format!("0{literal}"), 10),
+        _ => precise::parse(&literal, 10),
+    }?;
+    Some(Value::from_precise(value))
+}
+
 fn build_function_call(function_call: Pair<Rule>, pos: usize) -> Result<Token, Error> {
     let context = function_call.as_str().to_string();
 
@@ -243,15 +554,22 @@ fn build_function_call(function_call: Pair<Rule>, pos: usize) -> Result<Token, E
     let name = function_call.next().unwrap().as_str().to_string();
 
     let mut arguments: Vec<Expression> = Vec::new();
+    let mut named_arguments: Vec<(String, Expression)> = Vec::new();
     for pair in function_call {
         match pair.as_rule() {
-            Rule::expression => arguments.push(build_expression(pair)?),
+            Rule::expression | Rule::prefix_argument => arguments.push(build_expression(pair)?),
+            Rule::named_argument => {
+                let mut named_argument = pair.into_inner();
+                let name = named_argument.next().unwrap().as_str().to_string();
+                let expr = build_expression(named_argument.next().unwrap())?;
+                named_arguments.push((name, expr));
+            }
             _ => unreachable!(),
         }
     }
 
     Ok(Token::new_function_call(
         pos,
-        FunctionCall::new(context, name, arguments),
+        FunctionCall::new(context, name, arguments, named_arguments),
     ))
 }
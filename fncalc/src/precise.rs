@@ -0,0 +1,558 @@
+use crate::session;
+use bigdecimal::num_bigint::BigInt;
+use bigdecimal::{BigDecimal, Context, One, RoundingMode, Signed, ToPrimitive, Zero};
+use once_cell::sync::Lazy;
+use rust_decimal::Decimal;
+use std::num::NonZeroU64;
+use std::str::FromStr;
+use std::sync::Mutex;
+
+// digits calculated in addition to the precision, so that the rounding errors
+// of intermediate results don't show up in the rounded result
+const GUARD_DIGITS: u64 = 10;
+
+// precise values left over from the precise mode keep the precision of a Decimal
+const DECIMAL_DIGITS: u64 = 28;
+
+// e^x has about x / 2.3 digits before the decimal point, beyond this it is not calculated
+const EXP_LIMIT: i64 = 1_000_000_000;
+
+// literals of a larger magnitude would take too long to calculate with, like 1e999999999
+const LITERAL_MAGNITUDE_LIMIT: i64 = 10_000;
+
+// the reduction of larger angles would need more digits of pi than are worth calculating
+const ANGLE_MAGNITUDE_LIMIT: i64 = 10_000;
+
+static PI: Lazy<Mutex<Option<BigDecimal>>> = Lazy::new(|| Mutex::new(None));
+
+pub fn precision() -> u64 {
+    session::settings()
+        .number_mode
+        .precision()
+        .unwrap_or(DECIMAL_DIGITS)
+}
+
+fn context(digits: u64) -> Context {
+    Context::new(
+        NonZeroU64::new(digits).expect("The precision is never zero"),
+        RoundingMode::HalfEven,
+    )
+}
+
+fn working_context(precision: u64) -> Context {
+    context(precision + GUARD_DIGITS)
+}
+
+fn extended(ctx: &Context, extra_digits: u64) -> Context {
+    context(ctx.precision().get() + extra_digits)
+}
+
+// rounds to the given number of significant digits
+pub fn round(x: BigDecimal, precision: u64) -> BigDecimal {
+    context(precision).round_decimal(x).normalized()
+}
+
+pub fn from_decimal(x: Decimal) -> BigDecimal {
+    BigDecimal::new(BigInt::from(x.mantissa()), i64::from(x.scale()))
+}
+
+// None if the value is too large for a Decimal, digits beyond its precision are rounded
+pub fn to_decimal(x: &BigDecimal) -> Option<Decimal> {
+    if x.is_zero() {
+        return Some(Decimal::ZERO);
+    }
+    // checked first, so that the plain string of a huge value isn't built
+    let magnitude = x.order_of_magnitude();
+    if magnitude > 28 {
+        return None;
+    }
+    if magnitude < -29 {
+        return Some(Decimal::ZERO);
+    }
+    let x = x.with_scale_round(x.fractional_digit_count().min(28), RoundingMode::HalfEven);
+    Decimal::from_str(&x.to_plain_string()).ok()
+}
+
+// None if the value is not an integer
+pub fn to_i64(x: &BigDecimal) -> Option<i64> {
+    if x.is_integer() {
+        x.to_i64()
+    } else {
+        None
+    }
+}
+
+pub fn add(lhs: &BigDecimal, rhs: &BigDecimal, precision: u64) -> BigDecimal {
+    round(lhs + rhs, precision)
+}
+
+pub fn mul(lhs: &BigDecimal, rhs: &BigDecimal, precision: u64) -> BigDecimal {
+    round(lhs * rhs, precision)
+}
+
+// None if the divisor is zero
+pub fn div(lhs: &BigDecimal, rhs: &BigDecimal, precision: u64) -> Option<BigDecimal> {
+    if rhs.is_zero() {
+        return None;
+    }
+    Some(round(
+        divide(lhs, rhs, &working_context(precision)),
+        precision,
+    ))
+}
+
+fn divide(lhs: &BigDecimal, rhs: &BigDecimal, ctx: &Context) -> BigDecimal {
+    ctx.multiply(lhs, &ctx.invert(rhs))
+}
+
+// much faster than multiplying with the inverse, for the terms of the series
+fn divide_by_integer(x: &BigDecimal, n: u64, ctx: &Context) -> BigDecimal {
+    let shift = (ctx.precision().get() + 20).saturating_sub(x.digits());
+    let (digits, scale) = x.as_bigint_and_exponent();
+    let digits = digits * BigInt::from(10).pow(shift as u32) / BigInt::from(n);
+    ctx.round_decimal(BigDecimal::new(digits, scale + shift as i64))
+}
+
+// the remainder has the sign of the dividend, and is calculated exactly
+pub fn rem(lhs: &BigDecimal, rhs: &BigDecimal) -> BigDecimal {
+    let scale = lhs
+        .fractional_digit_count()
+        .max(rhs.fractional_digit_count());
+    let (lhs, _) = lhs.with_scale(scale).into_bigint_and_exponent();
+    let (rhs, _) = rhs.with_scale(scale).into_bigint_and_exponent();
+    BigDecimal::new(lhs % rhs, scale).normalized()
+}
+
+pub fn floor(x: &BigDecimal) -> BigDecimal {
+    x.with_scale_round(0, RoundingMode::Floor).normalized()
+}
+
+pub fn ceil(x: &BigDecimal) -> BigDecimal {
+    x.with_scale_round(0, RoundingMode::Ceiling).normalized()
+}
+
+pub fn trunc(x: &BigDecimal) -> BigDecimal {
+    x.with_scale_round(0, RoundingMode::Down).normalized()
+}
+
+// rounds half away from zero
+pub fn round_dp(x: &BigDecimal, dp: i64) -> BigDecimal {
+    x.with_scale_round(dp, RoundingMode::HalfUp).normalized()
+}
+
+// with exactly the given number of decimal places, rounded half away from zero
+pub fn fixed(x: &BigDecimal, dp: i64) -> String {
+    x.with_scale_round(dp, RoundingMode::HalfUp)
+        .to_plain_string()
+}
+
+// None for negative numbers
+pub fn sqrt(x: &BigDecimal, precision: u64) -> Option<BigDecimal> {
+    x.sqrt_with_context(&working_context(precision))
+        .map(|root| round(root, precision))
+}
+
+// None if the result is too large to be calculated
+pub fn exp(x: &BigDecimal, precision: u64) -> Option<BigDecimal> {
+    if x.abs() > EXP_LIMIT {
+        return if x.is_negative() {
+            Some(BigDecimal::zero())
+        } else {
+            None
+        };
+    }
+    Some(round(
+        exp_with_context(x, &working_context(precision)),
+        precision,
+    ))
+}
+
+// e^x = (e^(x / 2^n))^(2^n), where the series converges quickly for the small argument
+fn exp_with_context(x: &BigDecimal, ctx: &Context) -> BigDecimal {
+    let mut squarings = 0;
+    let mut reduced = x.clone();
+    let limit = BigDecimal::new(BigInt::one(), 3);
+    while reduced.abs() > limit {
+        reduced = reduced.half();
+        squarings += 1;
+    }
+
+    // each squaring doubles the error of the result
+    let ctx = extended(ctx, squarings / 3 + 1);
+    let mut sum = BigDecimal::one();
+    let mut term = BigDecimal::one();
+    let mut n = 0;
+    loop {
+        n += 1;
+        term = divide_by_integer(&ctx.multiply(&term, &reduced), n, &ctx);
+        if negligible(&term, &sum, &ctx) {
+            break;
+        }
+        sum = ctx.round_decimal(sum + &term);
+    }
+
+    for _ in 0..squarings {
+        sum = ctx.multiply(&sum, &sum);
+    }
+    sum
+}
+
+// None for numbers which are not positive
+pub fn ln(x: &BigDecimal, precision: u64) -> Option<BigDecimal> {
+    if !x.is_positive() {
+        return None;
+    }
+    Some(round(
+        ln_with_context(x, &working_context(precision)),
+        precision,
+    ))
+}
+
+// None if the argument or the base are not positive, the base must not be 1
+pub fn log(x: &BigDecimal, base: &BigDecimal, precision: u64) -> Option<BigDecimal> {
+    if !x.is_positive() || !base.is_positive() {
+        return None;
+    }
+    let ctx = working_context(precision);
+    let logarithm = divide(
+        &ln_with_context(x, &ctx),
+        &ln_with_context(base, &ctx),
+        &ctx,
+    );
+    Some(round(logarithm, precision))
+}
+
+fn ln_with_context(x: &BigDecimal, ctx: &Context) -> BigDecimal {
+    // x = m * 10^k with 1 <= m < 10, so that ln(x) = ln(m) + k ln(10)
+    let k = x.order_of_magnitude();
+    let (digits, scale) = x.as_bigint_and_exponent();
+    let m = BigDecimal::new(digits, scale + k);
+    if k == 0 {
+        return ln_reduced(&m, ctx);
+    }
+
+    let ctx = extended(ctx, k.unsigned_abs().to_string().len() as u64);
+    let ln_ten = ln_reduced(&BigDecimal::from(10), &ctx);
+    ctx.round_decimal(ln_reduced(&m, &ctx) + ctx.multiply(&BigDecimal::from(k), &ln_ten))
+}
+
+// square roots move the argument close to 1, where the series converges quickly
+fn ln_reduced(x: &BigDecimal, ctx: &Context) -> BigDecimal {
+    // each square root doubles the error of the result
+    let ctx = extended(ctx, 5);
+    let threshold = BigDecimal::new(BigInt::one(), 2);
+    let mut x = x.clone();
+    let mut factor = BigDecimal::from(2);
+    while (&x - BigDecimal::one()).abs() > threshold {
+        x = x
+            .sqrt_with_context(&ctx)
+            .expect("The argument of the logarithm is positive");
+        factor = factor.double();
+    }
+
+    // ln(x) = 2 atanh(z) with z = (x - 1) / (x + 1)
+    let z = divide(&(&x - BigDecimal::one()), &(&x + BigDecimal::one()), &ctx);
+    ctx.multiply(&odd_power_series(&z, false, &ctx), &factor)
+}
+
+// x + x^3/3 + x^5/5 + ..., with alternating signs this is the Taylor series of atan(x),
+// otherwise the one of atanh(x), both are meant for small arguments
+fn odd_power_series(x: &BigDecimal, alternating: bool, ctx: &Context) -> BigDecimal {
+    let mut x_squared = ctx.multiply(x, x);
+    if alternating {
+        x_squared = -x_squared;
+    }
+
+    let mut power = x.clone();
+    let mut sum = x.clone();
+    let mut n = 1;
+    loop {
+        power = ctx.multiply(&power, &x_squared);
+        n += 2;
+        let term = divide_by_integer(&power, n, ctx);
+        if negligible(&term, &sum, ctx) {
+            return sum;
+        }
+        sum = ctx.round_decimal(sum + term);
+    }
+}
+
+fn negligible(term: &BigDecimal, sum: &BigDecimal, ctx: &Context) -> bool {
+    term.is_zero()
+        || term.order_of_magnitude() < sum.order_of_magnitude() - ctx.precision().get() as i64
+}
+
+pub fn pi(precision: u64) -> BigDecimal {
+    round(pi_with_context(&working_context(precision)), precision)
+}
+
+// every trigonometric function needs pi, so the most precise value so far is kept
+fn pi_with_context(ctx: &Context) -> BigDecimal {
+    let mut cache = PI.lock().unwrap();
+    if let Some(pi) = cache
+        .as_ref()
+        .filter(|pi| pi.digits() >= ctx.precision().get())
+    {
+        return ctx.round_decimal_ref(pi);
+    }
+
+    let pi = machin(ctx);
+    *cache = Some(pi.clone());
+    pi
+}
+
+// Machin's formula, pi = 16 atan(1/5) - 4 atan(1/239)
+fn machin(ctx: &Context) -> BigDecimal {
+    let fifth = odd_power_series(&ctx.invert(&BigDecimal::from(5)), true, ctx);
+    let rest = odd_power_series(&ctx.invert(&BigDecimal::from(239)), true, ctx);
+    ctx.round_decimal(fifth * BigDecimal::from(16) - rest * BigDecimal::from(4))
+}
+
+pub fn tau(precision: u64) -> BigDecimal {
+    round(
+        pi_with_context(&working_context(precision)).double(),
+        precision,
+    )
+}
+
+pub fn e(precision: u64) -> BigDecimal {
+    round(
+        exp_with_context(&BigDecimal::one(), &working_context(precision)),
+        precision,
+    )
+}
+
+// (1 + sqrt(5)) / 2
+pub fn phi(precision: u64) -> BigDecimal {
+    let root = BigDecimal::from(5)
+        .sqrt_with_context(&working_context(precision))
+        .expect("5 is positive");
+    round((root + BigDecimal::one()).half(), precision)
+}
+
+// None if the angle is too large to be reduced
+pub fn sin(x: &BigDecimal, precision: u64) -> Option<BigDecimal> {
+    let (sin, _) = sin_cos(x, &working_context(precision))?;
+    Some(round(sin, precision))
+}
+
+pub fn cos(x: &BigDecimal, precision: u64) -> Option<BigDecimal> {
+    let (_, cos) = sin_cos(x, &working_context(precision))?;
+    Some(round(cos, precision))
+}
+
+// also None at the odd multiples of pi/2, where only the rounding error of the angle
+// is left in the cosine
+pub fn tan(x: &BigDecimal, precision: u64) -> Option<BigDecimal> {
+    let ctx = working_context(precision);
+    let (sin, cos) = sin_cos(x, &ctx)?;
+    let tolerance =
+        BigDecimal::new(BigInt::one(), precision as i64 - 1) * x.abs().max(BigDecimal::one());
+    if cos.abs() <= tolerance {
+        return None;
+    }
+    Some(round(divide(&sin, &cos, &ctx), precision))
+}
+
+// sine and cosine from their Taylor series, after reducing the angle to [-pi/4, pi/4]
+fn sin_cos(angle: &BigDecimal, ctx: &Context) -> Option<(BigDecimal, BigDecimal)> {
+    let magnitude = angle.order_of_magnitude();
+    if magnitude > ANGLE_MAGNITUDE_LIMIT {
+        return None;
+    }
+
+    // the digits before the decimal point are lost in the reduction
+    let ctx = extended(ctx, magnitude.max(0) as u64);
+    let half_pi = pi_with_context(&ctx).half();
+    let quadrant = divide(angle, &half_pi, &ctx).with_scale_round(0, RoundingMode::HalfEven);
+    let x = ctx.round_decimal(angle - ctx.multiply(&quadrant, &half_pi));
+
+    let x_squared = -ctx.multiply(&x, &x);
+    let mut sin = x.clone();
+    let mut cos = BigDecimal::one();
+    let mut sin_term = x;
+    let mut cos_term = BigDecimal::one();
+    let mut n: u64 = 0;
+    loop {
+        n += 2;
+        cos_term = divide_by_integer(&ctx.multiply(&cos_term, &x_squared), n * (n - 1), &ctx);
+        sin_term = divide_by_integer(&ctx.multiply(&sin_term, &x_squared), n * (n + 1), &ctx);
+        if negligible(&cos_term, &cos, &ctx) && negligible(&sin_term, &sin, &ctx) {
+            break;
+        }
+        sin = ctx.round_decimal(sin + &sin_term);
+        cos = ctx.round_decimal(cos + &cos_term);
+    }
+
+    let (quadrant, _) = quadrant.into_bigint_and_exponent();
+    match (quadrant % BigInt::from(4)).to_i8()? {
+        0 => Some((sin, cos)),
+        1 | -3 => Some((cos, -sin)),
+        2 | -2 => Some((-sin, -cos)),
+        _ => Some((-cos, sin)),
+    }
+}
+
+// None if the argument is outside of [-1, 1]
+pub fn asin(x: &BigDecimal, precision: u64) -> Option<BigDecimal> {
+    if x.abs() > BigDecimal::one() {
+        return None;
+    }
+
+    // asin(x) = 2 atan(x / (1 + sqrt(1 - x^2)))
+    let ctx = working_context(precision);
+    let one = BigDecimal::one();
+    let root = ctx
+        .multiply(&(&one - x), &(&one + x))
+        .sqrt_with_context(&ctx)?;
+    let angle = atan_with_context(&divide(x, &(one + root), &ctx), &ctx);
+    Some(round(angle.double(), precision))
+}
+
+pub fn acos(x: &BigDecimal, precision: u64) -> Option<BigDecimal> {
+    if x.abs() > BigDecimal::one() {
+        return None;
+    }
+
+    // acos(x) = 2 atan(sqrt((1 - x) / (1 + x)))
+    let ctx = working_context(precision);
+    let one = BigDecimal::one();
+    if *x == -one.clone() {
+        return Some(pi(precision));
+    }
+    let root = divide(&(&one - x), &(&one + x), &ctx).sqrt_with_context(&ctx)?;
+    Some(round(atan_with_context(&root, &ctx).double(), precision))
+}
+
+pub fn atan(x: &BigDecimal, precision: u64) -> BigDecimal {
+    round(atan_with_context(x, &working_context(precision)), precision)
+}
+
+fn atan_with_context(x: &BigDecimal, ctx: &Context) -> BigDecimal {
+    if x.is_negative() {
+        return -atan_with_context(&x.abs(), ctx);
+    }
+    if *x > BigDecimal::one() {
+        let angle = atan_with_context(&ctx.invert(x), ctx);
+        return ctx.round_decimal(pi_with_context(ctx).half() - angle);
+    }
+
+    // atan(x) = 2 atan(x / (1 + sqrt(1 + x^2))) halves the argument until the series converges quickly
+    if *x > BigDecimal::new(BigInt::one(), 1) {
+        let root = (BigDecimal::one() + ctx.multiply(x, x))
+            .sqrt_with_context(ctx)
+            .expect("1 + x^2 is positive");
+        return atan_with_context(&divide(x, &(root + BigDecimal::one()), ctx), ctx).double();
+    }
+
+    odd_power_series(x, true, ctx)
+}
+
+// the angle of the point (x, y)
+pub fn atan2(y: &BigDecimal, x: &BigDecimal, precision: u64) -> BigDecimal {
+    if x.is_zero() {
+        return match y.sign() {
+            bigdecimal::num_bigint::Sign::Plus => pi(precision).half(),
+            bigdecimal::num_bigint::Sign::Minus => -pi(precision).half(),
+            bigdecimal::num_bigint::Sign::NoSign => BigDecimal::zero(),
+        };
+    }
+
+    let ctx = working_context(precision);
+    let angle = atan_with_context(&divide(y, x, &ctx), &ctx);
+    let angle = if x.is_positive() {
+        angle
+    } else if y.is_negative() {
+        angle - pi_with_context(&ctx)
+    } else {
+        angle + pi_with_context(&ctx)
+    };
+    round(angle, precision)
+}
+
+// an angle in radians converted to a unit in which half a turn is the given angle, and back
+pub fn from_radians(x: &BigDecimal, half_turn: u32, precision: u64) -> BigDecimal {
+    let ctx = working_context(precision);
+    let turns = divide(x, &pi_with_context(&ctx), &ctx);
+    round(turns * BigDecimal::from(half_turn), precision)
+}
+
+pub fn to_radians(x: &BigDecimal, half_turn: u32, precision: u64) -> BigDecimal {
+    let ctx = working_context(precision);
+    let turns = divide(x, &BigDecimal::from(half_turn), &ctx);
+    round(ctx.multiply(&turns, &pi_with_context(&ctx)), precision)
+}
+
+// None if the power is not a real number, or too large to be calculated
+pub fn pow(x: &BigDecimal, y: &BigDecimal, precision: u64) -> Option<BigDecimal> {
+    if let Some(exponent) = to_i64(y) {
+        if x.is_zero() && exponent < 0 {
+            return None;
+        }
+        let power = x.powi_with_context(exponent, &working_context(precision));
+        return Some(round(power, precision));
+    }
+
+    if x.is_zero() {
+        return if y.is_positive() {
+            Some(BigDecimal::zero())
+        } else {
+            None
+        };
+    }
+    if x.is_negative() {
+        return None;
+    }
+
+    // x^y = e^(y ln(x)), where the digits of y ln(x) before the decimal point
+    // don't contribute to the precision of the result
+    let ctx = working_context(precision);
+    let mut exponent = ctx.multiply(y, &ln_with_context(x, &ctx));
+    let magnitude = exponent.order_of_magnitude();
+    if magnitude > 0 {
+        let ctx = extended(&ctx, magnitude as u64);
+        exponent = ctx.multiply(y, &ln_with_context(x, &ctx));
+    }
+    exp(&exponent, precision)
+}
+
+// plain notation, unless that would show more digits than the precision
+pub fn format(x: &BigDecimal, precision: u64) -> String {
+    let x = x.normalized();
+    let magnitude = x.order_of_magnitude();
+    if x.is_zero() || (magnitude < precision as i64 && magnitude >= -(precision as i64)) {
+        return x.to_plain_string();
+    }
+
+    let (digits, _) = x.as_bigint_and_exponent();
+    let sign = if digits.is_negative() { "-" } else { "" };
+    let digits = digits.magnitude().to_string();
+    if digits.len() > 1 {
+        format!("{sign}{}.{}e{magnitude}", &digits[..1], &digits[1..])
+    } else {
+        format!("{sign}{digits}e{magnitude}")
+    }
+}
+
+// how numbers left over from the precise mode are shown in the other modes, rounded
+// to 6 decimal places like a Decimal, or to 7 significant digits if that would be zero
+pub fn format_rounded(x: &BigDecimal) -> String {
+    let rounded = round_dp(x, 6);
+    if rounded.is_zero() && !x.is_zero() {
+        return format(&round(x.clone(), 7), 6);
+    }
+    format(&rounded, DECIMAL_DIGITS)
+}
+
+// None if the literal is not valid at all, or of a magnitude too large to calculate with
+pub fn parse(literal: &str, radix: u32) -> Option<BigDecimal> {
+    let x = if radix == 10 {
+        BigDecimal::from_str(literal).ok()
+    } else {
+        BigInt::parse_bytes(literal.as_bytes(), radix).map(|x| BigDecimal::new(x, 0))
+    }?;
+    if !x.is_zero() && x.order_of_magnitude().abs() > LITERAL_MAGNITUDE_LIMIT {
+        return None;
+    }
+    Some(x)
+}
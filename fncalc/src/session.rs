@@ -1,56 +1,456 @@
 #![allow(dead_code)]
 
-use crate::function::{Function, FunctionCall};
-use crate::instruction;
+use crate::builtin;
+use crate::function::{
+    self, Callable, Environment, Function, FunctionCall, Overload, Parameter, PendingCall,
+};
+use crate::instruction::{self, ReturnValue};
 use crate::Value;
-use crate::FUNCTION_CALL_LIMIT;
+use crate::{FUNCTION_CALL_LIMIT, LOOP_LIMIT};
 use once_cell::sync::Lazy;
+use rust_decimal::Decimal;
 use std::collections::HashMap;
-use std::sync::{Mutex, RwLock};
+use std::sync::{Arc, Mutex, RwLock};
 
-static GLOBAL_NAMESPACE: Lazy<Mutex<HashMap<String, Value>>> =
-    Lazy::new(|| Mutex::new(HashMap::new()));
+// namespaces are shared with the closures created in them, and only copied
+// when they are changed while a closure still holds them
+static GLOBAL_NAMESPACE: Lazy<Mutex<Arc<HashMap<String, Value>>>> =
+    Lazy::new(|| Mutex::new(Arc::default()));
 
-static LOCAL_NAMESPACES: Lazy<Mutex<Vec<HashMap<String, Value>>>> =
+static LOCAL_NAMESPACES: Lazy<Mutex<Vec<Frame>>> =
     Lazy::new(|| Mutex::new(Vec::new()));
 
-static FUNCTION_STORE: Lazy<RwLock<HashMap<String, Function>>> =
+// the overloads of each function, sorted by their number of parameters
+static FUNCTION_STORE: Lazy<RwLock<HashMap<String, Vec<Overload>>>> =
     Lazy::new(|| RwLock::new(HashMap::new()));
 
 static CALL_COUNT: Lazy<Mutex<usize>> =
     Lazy::new(||Mutex::new(0));
 
+static SETTINGS: Lazy<Mutex<Settings>> = Lazy::new(|| Mutex::new(Settings::default()));
+
+// the namespace of a call: the variables set in it, the ones its function captured
+// and the functions defined in it
+struct Frame {
+    variables: Arc<HashMap<String, Value>>,
+    captured: Vec<Arc<HashMap<String, Value>>>,
+    scope: Arc<Scope>,
+}
+
+impl Frame {
+    fn variables(&self) -> Vec<Arc<HashMap<String, Value>>> {
+        let mut variables = vec![self.variables.clone()];
+        variables.extend(self.captured.iter().cloned());
+        variables
+    }
+}
+
+// the functions defined inside a call and the calls around it. Closures hold the scope
+// they were created in, so that they can call functions defined after them.
+#[derive(Debug, Default)]
+pub struct Scope {
+    functions: RwLock<HashMap<String, Vec<Overload>>>,
+    parent: Option<Arc<Scope>>,
+}
+
+impl Scope {
+    // the functions are stored without their scope, which would keep itself alive
+    fn get(self: &Arc<Scope>, name: &str) -> Option<Callable> {
+        let mut scope = Some(self);
+        while let Some(current) = scope {
+            if let Some(overloads) = current.functions.read().unwrap().get(name) {
+                let mut overloads = overloads.clone();
+                for overload in &mut overloads {
+                    overload.environment.scope = Some(current.clone());
+                }
+                return Some(callable(name, overloads));
+            }
+            scope = current.parent.as_ref();
+        }
+        None
+    }
+}
+
+impl PartialEq for Scope {
+    fn eq(&self, other: &Scope) -> bool {
+        std::ptr::eq(self, other)
+            || (*self.functions.read().unwrap() == *other.functions.read().unwrap()
+                && self.parent == other.parent)
+    }
+}
+
+// settings are not affected by clearing the session
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Settings {
+    pub legacy_precedence: bool,
+    pub physical_constants: bool,
+    pub angle_mode: AngleMode,
+    pub complex_numbers: bool,
+    pub number_mode: NumberMode,
+    pub overflow: Overflow,
+    pub radix: Radix,
+}
+
+// a 'mode' statement in a script
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Angle(AngleMode),
+    Number(NumberMode),
+    Overflow(Overflow),
+    Radix(Radix),
+}
+
+impl Mode {
+    pub fn apply(&self) {
+        let mut settings = settings();
+        match self {
+            Mode::Angle(mode) => settings.angle_mode = *mode,
+            Mode::Number(mode) => settings.number_mode = *mode,
+            Mode::Overflow(overflow) => settings.overflow = *overflow,
+            Mode::Radix(radix) => settings.radix = *radix,
+        }
+        set_settings(settings);
+    }
+}
+
+impl std::fmt::Display for Mode {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Mode::Angle(mode) => write!(f, "mode {mode}"),
+            Mode::Number(NumberMode::Precise(digits)) => write!(f, "precision {digits}"),
+            Mode::Number(NumberMode::Integer(integer_type)) => write!(f, "mode {integer_type}"),
+            Mode::Number(mode) => write!(f, "mode {mode}"),
+            Mode::Overflow(overflow) => write!(f, "mode {overflow}"),
+            Mode::Radix(radix) => write!(f, "mode {radix}"),
+        }
+    }
+}
+
+// in the exact mode, number literals are fractions and calculations
+// without transcendental functions have exact results, in the precise mode
+// numbers have the given number of significant digits instead of the 28 of a Decimal,
+// in the float mode they are f64 numbers with IEEE semantics like inf and NaN,
+// and in the integer mode of programmers integers of a fixed width
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NumberMode {
+    #[default]
+    Decimal,
+    Exact,
+    Precise(u64),
+    Float,
+    Integer(IntegerType),
+}
+
+impl NumberMode {
+    pub fn precision(&self) -> Option<u64> {
+        match self {
+            NumberMode::Precise(digits) => Some(*digits),
+            _ => None,
+        }
+    }
+}
+
+impl std::str::FromStr for NumberMode {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<NumberMode, ()> {
+        match s {
+            "decimal" => Ok(NumberMode::Decimal),
+            "exact" => Ok(NumberMode::Exact),
+            "float" => Ok(NumberMode::Float),
+            _ => Ok(NumberMode::Integer(s.parse()?)),
+        }
+    }
+}
+
+impl std::fmt::Display for NumberMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            NumberMode::Decimal => write!(f, "decimal"),
+            NumberMode::Exact => write!(f, "exact"),
+            NumberMode::Precise(digits) => write!(f, "precise ({digits} digits)"),
+            NumberMode::Float => write!(f, "float"),
+            NumberMode::Integer(integer_type) => write!(f, "integer ({integer_type})"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IntegerType {
+    I8,
+    I16,
+    I32,
+    I64,
+    U8,
+    U16,
+    U32,
+    U64,
+}
+
+impl IntegerType {
+    pub fn bits(&self) -> u32 {
+        match self {
+            IntegerType::I8 | IntegerType::U8 => 8,
+            IntegerType::I16 | IntegerType::U16 => 16,
+            IntegerType::I32 | IntegerType::U32 => 32,
+            IntegerType::I64 | IntegerType::U64 => 64,
+        }
+    }
+
+    pub fn is_signed(&self) -> bool {
+        matches!(
+            self,
+            IntegerType::I8 | IntegerType::I16 | IntegerType::I32 | IntegerType::I64
+        )
+    }
+
+    pub fn min(&self) -> i128 {
+        if self.is_signed() {
+            -(1 << (self.bits() - 1))
+        } else {
+            0
+        }
+    }
+
+    pub fn max(&self) -> i128 {
+        if self.is_signed() {
+            (1 << (self.bits() - 1)) - 1
+        } else {
+            (1 << self.bits()) - 1
+        }
+    }
+}
+
+impl std::str::FromStr for IntegerType {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<IntegerType, ()> {
+        match s {
+            "i8" => Ok(IntegerType::I8),
+            "i16" => Ok(IntegerType::I16),
+            "i32" => Ok(IntegerType::I32),
+            "i64" => Ok(IntegerType::I64),
+            "u8" => Ok(IntegerType::U8),
+            "u16" => Ok(IntegerType::U16),
+            "u32" => Ok(IntegerType::U32),
+            "u64" => Ok(IntegerType::U64),
+            _ => Err(()),
+        }
+    }
+}
+
+impl std::fmt::Display for IntegerType {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let sign = if self.is_signed() { "i" } else { "u" };
+        write!(f, "{sign}{}", self.bits())
+    }
+}
+
+// what happens to results of the integer mode which don't fit into the integer type
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Overflow {
+    #[default]
+    Error,
+    Wrap,
+}
+
+impl std::str::FromStr for Overflow {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Overflow, ()> {
+        match s {
+            "checked" => Ok(Overflow::Error),
+            "wrap" => Ok(Overflow::Wrap),
+            _ => Err(()),
+        }
+    }
+}
+
+impl std::fmt::Display for Overflow {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Overflow::Error => write!(f, "checked"),
+            Overflow::Wrap => write!(f, "wrap"),
+        }
+    }
+}
+
+// the base in which results of the integer mode are printed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Radix {
+    #[default]
+    Decimal,
+    Hex,
+    Binary,
+    Octal,
+}
+
+impl std::str::FromStr for Radix {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Radix, ()> {
+        match s {
+            "dec" => Ok(Radix::Decimal),
+            "hex" => Ok(Radix::Hex),
+            "bin" => Ok(Radix::Binary),
+            "oct" => Ok(Radix::Octal),
+            _ => Err(()),
+        }
+    }
+}
+
+impl std::fmt::Display for Radix {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Radix::Decimal => write!(f, "dec"),
+            Radix::Hex => write!(f, "hex"),
+            Radix::Binary => write!(f, "bin"),
+            Radix::Octal => write!(f, "oct"),
+        }
+    }
+}
+
+// the unit in which trigonometric functions take and return angles
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AngleMode {
+    #[default]
+    Radians,
+    Degrees,
+    Gradians,
+}
+
+impl AngleMode {
+    // the angle of half a turn in this unit, None for radians
+    pub fn half_turn(&self) -> Option<u32> {
+        match self {
+            AngleMode::Radians => None,
+            AngleMode::Degrees => Some(180),
+            AngleMode::Gradians => Some(200),
+        }
+    }
+
+    pub fn to_radians(&self, angle: Decimal) -> Decimal {
+        match self {
+            AngleMode::Radians => angle,
+            AngleMode::Degrees => angle * (Decimal::PI / Decimal::from(180)),
+            AngleMode::Gradians => angle * (Decimal::PI / Decimal::from(200)),
+        }
+    }
+
+    pub fn from_radians(&self, angle: Decimal) -> Decimal {
+        match self {
+            AngleMode::Radians => angle,
+            AngleMode::Degrees => angle * (Decimal::from(180) / Decimal::PI),
+            AngleMode::Gradians => angle * (Decimal::from(200) / Decimal::PI),
+        }
+    }
+}
+
+impl std::str::FromStr for AngleMode {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<AngleMode, ()> {
+        match s {
+            "rad" => Ok(AngleMode::Radians),
+            "deg" => Ok(AngleMode::Degrees),
+            "grad" => Ok(AngleMode::Gradians),
+            _ => Err(()),
+        }
+    }
+}
+
+impl std::fmt::Display for AngleMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            AngleMode::Radians => write!(f, "rad"),
+            AngleMode::Degrees => write!(f, "deg"),
+            AngleMode::Gradians => write!(f, "grad"),
+        }
+    }
+}
+
+pub fn settings() -> Settings {
+    *SETTINGS.lock().unwrap()
+}
+
+pub fn set_settings(settings: Settings) {
+    *SETTINGS.lock().unwrap() = settings;
+}
+
+// the variables set in a call shadow the captured ones
 pub fn get_variable(var_name: &str) -> Option<Value> {
     let local_namespaces = LOCAL_NAMESPACES.lock().unwrap();
-    let len = local_namespaces.len();
-    if len > 0 {
-        local_namespaces[len - 1].get(var_name).copied()
-    } else {
-        GLOBAL_NAMESPACE.lock().unwrap().get(var_name).copied()
+    match local_namespaces.last() {
+        Some(frame) => std::iter::once(&frame.variables)
+            .chain(&frame.captured)
+            .find_map(|variables| variables.get(var_name))
+            .cloned(),
+        None => GLOBAL_NAMESPACE.lock().unwrap().get(var_name).cloned(),
     }
 }
 
 pub fn set_variable(var_name: &str, val: Value) {
     let mut local_namespaces = LOCAL_NAMESPACES.lock().unwrap();
-    let len = local_namespaces.len();
-    if len > 0 {
-        local_namespaces[len - 1].insert(var_name.to_string(), val);
-    } else {
-        GLOBAL_NAMESPACE
-            .lock()
-            .unwrap()
-            .insert(var_name.to_string(), val);
+    match local_namespaces.last_mut() {
+        Some(frame) => Arc::make_mut(&mut frame.variables).insert(var_name.to_string(), val),
+        None => Arc::make_mut(&mut GLOBAL_NAMESPACE.lock().unwrap())
+            .insert(var_name.to_string(), val),
+    };
+}
+
+pub fn remove_variable(var_name: &str) {
+    let mut local_namespaces = LOCAL_NAMESPACES.lock().unwrap();
+    match local_namespaces.last_mut() {
+        Some(frame) => Arc::make_mut(&mut frame.variables).remove(var_name),
+        None => Arc::make_mut(&mut GLOBAL_NAMESPACE.lock().unwrap()).remove(var_name),
+    };
+}
+
+// the current namespace, for closures
+pub fn environment() -> Environment {
+    let local_namespaces = LOCAL_NAMESPACES.lock().unwrap();
+    match local_namespaces.last() {
+        Some(frame) => Environment {
+            variables: frame.variables(),
+            scope: Some(frame.scope.clone()),
+        },
+        None => Environment {
+            variables: vec![GLOBAL_NAMESPACE.lock().unwrap().clone()],
+            scope: None,
+        },
     }
 }
 
+// a function defined inside a call, which captures the variables set so far
+pub fn define_function(function: &Arc<Function>) {
+    let local_namespaces = LOCAL_NAMESPACES.lock().unwrap();
+    let frame = local_namespaces
+        .last()
+        .expect("Functions are only defined inside calls at runtime");
+    let name = function.name().expect("Defined functions have a name");
+    let overload = Overload {
+        function: function.clone(),
+        environment: Environment {
+            variables: frame.variables(),
+            scope: None,
+        },
+    };
+    merge(&mut frame.scope.functions.write().unwrap(), name.to_string(), overload);
+}
+
 pub fn clear() {
-    GLOBAL_NAMESPACE.lock().unwrap().clear();
+    *GLOBAL_NAMESPACE.lock().unwrap() = Arc::default();
     LOCAL_NAMESPACES.lock().unwrap().clear();
     FUNCTION_STORE.write().unwrap().clear();
 }
 
-fn add_namespace(namespace: HashMap<String, Value>) {
-    LOCAL_NAMESPACES.lock().unwrap().push(namespace);
+// whether a function is being called, rather than the script running at the top level
+pub fn in_function() -> bool {
+    !LOCAL_NAMESPACES.lock().unwrap().is_empty()
+}
+
+fn add_namespace(frame: Frame) {
+    LOCAL_NAMESPACES.lock().unwrap().push(frame);
 }
 
 fn pop_namespace() {
@@ -58,7 +458,43 @@ fn pop_namespace() {
 }
 
 pub fn add_function(name: String, value: Function) {
-    FUNCTION_STORE.write().unwrap().insert(name, value);
+    let overload = Overload {
+        function: Arc::new(value),
+        environment: Environment::default(),
+    };
+    merge(&mut FUNCTION_STORE.write().unwrap(), name, overload);
+}
+
+// a function replaces the one with the same name and number of parameters
+fn merge(store: &mut HashMap<String, Vec<Overload>>, name: String, overload: Overload) {
+    let overloads = store.entry(name).or_default();
+    overloads.retain(|o| !o.function.overloads(&overload.function));
+    overloads.push(overload);
+    overloads.sort_by_key(|o| o.function.parameters().len());
+}
+
+fn callable(name: &str, overloads: Vec<Overload>) -> Callable {
+    match <[Overload; 1]>::try_from(overloads) {
+        Ok([overload]) => Callable::Function(overload.function, overload.environment),
+        Err(overloads) => Callable::Overloaded(name.to_string(), overloads),
+    }
+}
+
+pub fn has_function(name: &str) -> bool {
+    get_function(name).is_some()
+}
+
+// functions defined inside the current call and the calls around it shadow the ones
+// defined at the top level, and user defined functions shadow the built-in ones
+pub fn get_function(name: &str) -> Option<Callable> {
+    let scope = LOCAL_NAMESPACES.lock().unwrap().last().map(|frame| frame.scope.clone());
+    if let Some(function) = scope.and_then(|scope| scope.get(name)) {
+        return Some(function);
+    }
+    match FUNCTION_STORE.read().unwrap().get(name) {
+        Some(overloads) => Some(callable(name, overloads.clone())),
+        None => builtin::get(name).map(Callable::Builtin),
+    }
 }
 
 fn increment_call_count() -> Result<(), instruction::Error> {
@@ -78,43 +514,208 @@ fn decrement_call_count() {
 
 pub fn call_function(
     fncall: &FunctionCall,
+    context: &str,
     pos: usize,
     output_stream: &mut String,
 ) -> instruction::Result {
-    let fnstore = FUNCTION_STORE.read().unwrap();
-    let function = match fnstore.get(fncall.name()) {
-        Some(f) => f,
-        None => {
+    let pending = prepare_call(fncall, context, pos, output_stream)?;
+    call(
+        &pending.function,
+        &pending.arguments,
+        &pending.named_arguments,
+        context,
+        pos,
+        output_stream,
+    )
+}
+
+// the function to call and its evaluated arguments
+pub fn prepare_call(
+    fncall: &FunctionCall,
+    context: &str,
+    pos: usize,
+    output_stream: &mut String,
+) -> Result<PendingCall, instruction::Error> {
+    // variables holding functions shadow the functions with the same name
+    let function = match get_variable(fncall.name()) {
+        Some(Value::Function(function)) => function,
+        _ => match get_function(fncall.name()) {
+            Some(function) => function,
+            None => {
+                return Err(instruction::Error::new(
+                    context.to_string(),
+                    pos,
+                    instruction::ErrorKind::IdentifierNotFound,
+                ))
+            }
+        },
+    };
+
+    // the number of arguments of a built-in function is checked before they are evaluated
+    if let Callable::Builtin(builtin) = &function {
+        let count = fncall.arguments().len();
+        if fncall.named_arguments().is_empty() && !builtin.arity().accepts(count) {
             return Err(instruction::Error::new(
-                fncall.context().to_string(),
+                context.to_string(),
                 pos,
-                instruction::ErrorKind::IdentifierNotFound,
+                instruction::ErrorKind::InvalidNumberOfArgument,
             ));
         }
-    };
-
-    if function.argument_names().len() != fncall.arguments().len() {
-        return Err(instruction::Error::new(
-            fncall.context().to_string(),
-            pos,
-            instruction::ErrorKind::InvalidNumberOfArgument,
-        ));
     }
 
-    let mut fn_namespace: HashMap<String, Value> = HashMap::new();
-    for i in 0..function.argument_names().len() {
-        let expr = &fncall.arguments()[i];
-        let result = expr
+    let mut arguments: Vec<Value> = Vec::new();
+    for expr in fncall.arguments() {
+        arguments.push(
+            expr.exec(output_stream)?
+                .expect("Expressions should always return a value on success"),
+        );
+    }
+    let mut named_arguments: Vec<(String, Value)> = Vec::new();
+    for (name, expr) in fncall.named_arguments() {
+        let value = expr
             .exec(output_stream)?
             .expect("Expressions should always return a value on success");
+        named_arguments.push((name.clone(), value));
+    }
 
-        fn_namespace.insert(function.argument_names()[i].clone(), result);
+    Ok(PendingCall {
+        function,
+        arguments,
+        named_arguments,
+        context: context.to_string(),
+        pos,
+    })
+}
+
+// evaluated in order, so that defaults can refer to the parameters before them
+fn set_defaults(
+    parameters: &[&Parameter],
+    output_stream: &mut String,
+) -> Result<(), instruction::Error> {
+    for parameter in parameters {
+        if let Some(default) = parameter.default() {
+            let value = default
+                .exec(output_stream)?
+                .expect("Expressions should always return a value on success");
+            set_variable(parameter.name(), value);
+        }
     }
+    Ok(())
+}
 
+pub fn call(
+    function: &Callable,
+    arguments: &[Value],
+    named_arguments: &[(String, Value)],
+    context: &str,
+    pos: usize,
+    output_stream: &mut String,
+) -> instruction::Result {
+    if let Callable::Builtin(_) = function {
+        return enter(
+            function,
+            arguments,
+            named_arguments,
+            context,
+            pos,
+            output_stream,
+        );
+    }
+
+    // a tail call takes the place of the call which returned it, so that tail recursion
+    // neither grows the stack nor counts towards the limit of nested calls
     increment_call_count()?;
-    add_namespace(fn_namespace);
-    let result = function.exec(output_stream);
-    pop_namespace();
+    let mut result = enter(
+        function,
+        arguments,
+        named_arguments,
+        context,
+        pos,
+        output_stream,
+    );
+    let mut tail_calls = 0;
+    while let Ok(ReturnValue::TailCall(tail_call)) = result {
+        tail_calls += 1;
+        if tail_calls > LOOP_LIMIT {
+            result = Err(instruction::Error::new(
+                String::new(),
+                0,
+                instruction::ErrorKind::IterationLimitReached,
+            ));
+            break;
+        }
+
+        let PendingCall {
+            function,
+            arguments,
+            named_arguments,
+            context,
+            pos,
+        } = *tail_call;
+        result = match enter(
+            &function,
+            &arguments,
+            &named_arguments,
+            &context,
+            pos,
+            output_stream,
+        ) {
+            Ok(ReturnValue::None) => Err(instruction::Error::new(
+                context,
+                pos,
+                instruction::ErrorKind::MissingReturnValue,
+            )),
+            result => result,
+        };
+    }
     decrement_call_count();
-    return result;
+    result
+}
+
+// a single call, which may return a tail call instead of a value
+fn enter(
+    function: &Callable,
+    arguments: &[Value],
+    named_arguments: &[(String, Value)],
+    context: &str,
+    pos: usize,
+    output_stream: &mut String,
+) -> instruction::Result {
+    let error = |kind| instruction::Error::new(context.to_string(), pos, kind);
+
+    let (function, environment) = match function {
+        // the parameters of built-in functions have no names
+        Callable::Builtin(builtin) => {
+            if let Some((name, _)) = named_arguments.first() {
+                return Err(error(instruction::ErrorKind::UnexpectedArgument(
+                    name.clone(),
+                )));
+            }
+            if !builtin.arity().accepts(arguments.len()) {
+                return Err(error(instruction::ErrorKind::InvalidNumberOfArgument));
+            }
+            return builtin.call(arguments, context, pos, output_stream);
+        }
+        Callable::Function(function, environment) => (function, environment.clone()),
+        Callable::Overloaded(_, overloads) => {
+            let count = arguments.len() + named_arguments.len();
+            let overload = function::overload(overloads, count).map_err(error)?;
+            (&overload.function, overload.environment.clone())
+        }
+    };
+    let (values, defaults) = function.bind(arguments, named_arguments).map_err(error)?;
+
+    // nested functions find themselves and the functions next to them in the scope
+    // they were defined in
+    add_namespace(Frame {
+        variables: Arc::new(values),
+        captured: environment.variables,
+        scope: Arc::new(Scope {
+            functions: RwLock::default(),
+            parent: environment.scope,
+        }),
+    });
+    let result = set_defaults(&defaults, output_stream).and_then(|_| function.exec(output_stream));
+    pop_namespace();
+    result
 }
@@ -1,7 +1,20 @@
-use crate::{process, reset_session};
+use crate::{process, reset_session, session};
+use std::sync::{Mutex, MutexGuard};
+
+// the session is global, so tests touching it must not run concurrently
+static SESSION: Mutex<()> = Mutex::new(());
+
+fn lock_session() -> MutexGuard<'static, ()> {
+    let guard = SESSION.lock().unwrap_or_else(|e| e.into_inner());
+    session::set_settings(session::Settings::default());
+    reset_session();
+    guard
+}
 
 #[test]
 fn invalid_math() {
+    let _session = lock_session();
+
     let result = process("1 / 0");
     let expected = "1 / 0\n  ^\nError: Division by zero";
     assert_eq!(result, expected);
@@ -11,52 +24,62 @@ fn invalid_math() {
     assert_eq!(result, expected);
 
     let result = process("tan(pi/2)");
-    let expected = "tan(pi/2)\n^\nError: Math error";
+    let expected = "tan(pi/2)\n^\nError: Argument outside of the function's domain";
+    assert_eq!(result, expected);
+
+    let result = process("tan(0)");
+    let expected = "0";
+    assert_eq!(result, expected);
+
+    let result = process("tan(3 * pi/2)");
+    let expected = "tan(3 * pi/2)\n^\nError: Argument outside of the function's domain";
     assert_eq!(result, expected);
 
     let result = process("tan(5 * pi/2)");
-    let expected = "tan(5 * pi/2)\n^\nError: Math error";
+    let expected = "tan(5 * pi/2)\n^\nError: Argument outside of the function's domain";
     assert_eq!(result, expected);
 
     let result = process("tand(90)");
-    let expected = "tand(90)\n^\nError: Math error";
+    let expected = "tand(90)\n^\nError: Argument outside of the function's domain";
     assert_eq!(result, expected);
 
     let result = process("tand(5 * 90)");
-    let expected = "tand(5 * 90)\n^\nError: Math error";
+    let expected = "tand(5 * 90)\n^\nError: Argument outside of the function's domain";
     assert_eq!(result, expected);
 
     let result = process("tand(90 + 180 * 3)");
-    let expected = "tand(90 + 180 * 3)\n^\nError: Math error";
+    let expected = "tand(90 + 180 * 3)\n^\nError: Argument outside of the function's domain";
     assert_eq!(result, expected);
 
     let result = process("asin 2");
-    let expected = "asin 2\n^\nError: Math error";
+    let expected = "asin 2\n^\nError: Argument outside of the function's domain";
     assert_eq!(result, expected);
 
     let result = process("acos -2");
-    let expected = "acos -2\n^\nError: Math error";
+    let expected = "acos -2\n^\nError: Argument outside of the function's domain";
     assert_eq!(result, expected);
 
     let result = process("asind -2");
-    let expected = "asind -2\n^\nError: Math error";
+    let expected = "asind -2\n^\nError: Argument outside of the function's domain";
     assert_eq!(result, expected);
 
     let result = process("acosd 2");
-    let expected = "acosd 2\n^\nError: Math error";
+    let expected = "acosd 2\n^\nError: Argument outside of the function's domain";
     assert_eq!(result, expected);
 
     let result = process("ln 0");
-    let expected = "ln 0\n^\nError: Math error";
+    let expected = "ln 0\n^\nError: Argument outside of the function's domain";
     assert_eq!(result, expected);
 
     let result = process("log -1");
-    let expected = "log -1\n^\nError: Math error";
+    let expected = "log -1\n^\nError: Argument outside of the function's domain";
     assert_eq!(result, expected);
 }
 
 #[test]
 fn expression() {
+    let _session = lock_session();
+
     let result = process("");
     let expected = "";
     assert_eq!(result, expected);
@@ -78,7 +101,7 @@ fn expression() {
     assert_eq!(result, expected);
 
     let result = process("2 + -2 ^ 8");
-    let expected = "258";
+    let expected = "-254";
     assert_eq!(result, expected);
 
     let result = process("10/5");
@@ -86,7 +109,7 @@ fn expression() {
     assert_eq!(result, expected);
 
     let result = process("2 + -2 ^ 8");
-    let expected = "258";
+    let expected = "-254";
     assert_eq!(result, expected);
 
     let result = process("1 / 5");
@@ -201,7 +224,8 @@ fn expression() {
     let expected = "2";
     assert_eq!(result, expected);
 
-    let result = process("sind90^2");
+    // function names only match whole words
+    let result = process("sind 90^2");
     let expected = "1";
     assert_eq!(result, expected);
 
@@ -300,6 +324,8 @@ fn expression() {
 
 #[test]
 fn scripting() {
+    let _session = lock_session();
+
     // variables
     let result = process("x");
     let expected = "x\n^\nError: Identifier not found";
@@ -423,16 +449,18 @@ print add_one(count_up())");
     assert_eq!(result, expected);
 
     let result = process("fn f(x) {x}\nf()");
-    let expected = "f()\n^\nError: Invalid number of arguments passed to function";
+    let expected = "f()\n^\nError: Invalid number of arguments passed to function, 'x' is missing";
     assert_eq!(result, expected);
 
     let result = process("fn f(x) {x}\nf(1, 2)");
-    let expected = "f(1, 2)\n^\nError: Invalid number of arguments passed to function";
+    let expected = "f(1, 2)\n^\nError: Invalid number of arguments passed to function, expected 1";
     assert_eq!(result, expected);
 }
 
 #[test]
 fn iteration_limit() {
+    let _session = lock_session();
+
     let result = process("while 1 {}");
     let expected = "Error: Maximum iteration count reached";
     assert_eq!(result, expected);
@@ -441,3 +469,2677 @@ fn iteration_limit() {
     let expected = "Error: Maximum iteration count reached";
     assert_eq!(result, expected);
 }
+
+#[test]
+fn conditional_expression() {
+    let _session = lock_session();
+
+    let result = process("if 1 then 2 else 3");
+    let expected = "2";
+    assert_eq!(result, expected);
+
+    let result = process("if 0 then 2 else 3");
+    let expected = "3";
+    assert_eq!(result, expected);
+
+    let result = process("x = -4; y = if x > 0 then x else -x");
+    let expected = "4";
+    assert_eq!(result, expected);
+
+    let result = process("1 + if 2 > 1 then 10 else 20");
+    let expected = "11";
+    assert_eq!(result, expected);
+
+    let result = process("(if 0 then 1 else 2) * 3");
+    let expected = "6";
+    assert_eq!(result, expected);
+
+    let result = process("if 1 then if 0 then 1 else 2 else 3");
+    let expected = "2";
+    assert_eq!(result, expected);
+
+    let result = process("fn f(x) { return if x < 0 then -x else x }\nf(-3) + f(3)");
+    let expected = "6";
+    assert_eq!(result, expected);
+
+    // only the selected arm is evaluated
+    let result = process("if 1 then 5 else 1 / 0");
+    let expected = "5";
+    assert_eq!(result, expected);
+
+    let result = process("fn loud(x) { print x; x }\nif 0 then loud(1) else loud(2)");
+    let expected = "2";
+    assert_eq!(result, expected);
+
+    let result = process("x = 3; if x > 10 {x = x + 10} else { x = x - 1 } x");
+    let expected = "2";
+    assert_eq!(result, expected);
+
+    let result = process("if 0 then 1 else 2 / 0");
+    let expected = "2 / 0\n  ^\nError: Division by zero";
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn implicit_multiplication() {
+    let _session = lock_session();
+
+    let result = process("2pi");
+    let expected = "6.283185";
+    assert_eq!(result, expected);
+
+    let result = process("x = 3; 2x");
+    let expected = "6";
+    assert_eq!(result, expected);
+
+    let result = process("x = 3; 4(x + 1)");
+    let expected = "16";
+    assert_eq!(result, expected);
+
+    let result = process("(2 + 1)(3 - 1)");
+    let expected = "6";
+    assert_eq!(result, expected);
+
+    let result = process("a = 2; b = 5; (a)(b)");
+    let expected = "10";
+    assert_eq!(result, expected);
+
+    let result = process("2.5(4)");
+    let expected = "10";
+    assert_eq!(result, expected);
+
+    let result = process("(2)3");
+    let expected = "6";
+    assert_eq!(result, expected);
+
+    let result = process("x = 2; (x + 1)2");
+    let expected = "6";
+    assert_eq!(result, expected);
+
+    // binds tighter than * and /, but looser than ^ and unary operators
+    let result = process("x = 2; 1/2x");
+    let expected = "0.25";
+    assert_eq!(result, expected);
+
+    let result = process("x = 4; 8/2x*2");
+    let expected = "2";
+    assert_eq!(result, expected);
+
+    let result = process("x = 3; 2x^2");
+    let expected = "18";
+    assert_eq!(result, expected);
+
+    let result = process("x = 3; -2x");
+    let expected = "-6";
+    assert_eq!(result, expected);
+
+    // only direct juxtaposition multiplies, whitespace separates instructions
+    let result = process("x = 3\nx + 1");
+    let expected = "4";
+    assert_eq!(result, expected);
+
+    let result = process("x = 5\n(x)");
+    let expected = "5";
+    assert_eq!(result, expected);
+
+    // calls stay calls if the function exists
+    let result = process("fn f(x) { x + 1 }\nf(2)");
+    let expected = "3";
+    assert_eq!(result, expected);
+
+    let result = process("fn f(x) { x + 1 }\n2f(2)(3)");
+    let expected = "18";
+    assert_eq!(result, expected);
+
+    let result = process("k = 2; k(3)");
+    let expected = "6";
+    assert_eq!(result, expected);
+
+    let result = process("k = 2; k(3, 4)");
+    let expected = "k(3, 4)\n^\nError: Identifier not found";
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn number_literals() {
+    let _session = lock_session();
+
+    let result = process("6.022e23");
+    let expected = "602200000000000000000000";
+    assert_eq!(result, expected);
+
+    let result = process("2.5E+2 + 1e-3");
+    let expected = "250.001";
+    assert_eq!(result, expected);
+
+    let result = process(".5 + 1");
+    let expected = "1.5";
+    assert_eq!(result, expected);
+
+    let result = process("1_000_000");
+    let expected = "1000000";
+    assert_eq!(result, expected);
+
+    let result = process("0xFF + 0b1010 + 0o17");
+    let expected = "280";
+    assert_eq!(result, expected);
+
+    let result = process("1e30");
+    let expected = "1e30\n^\nError: Number literal out of range";
+    assert_eq!(result, expected);
+
+    let result = process("x = 1 + 99999999999999999999999999999");
+    let expected = "x = 1 + 99999999999999999999999999999\n        ^\nError: Number literal out of range";
+    assert_eq!(result, expected);
+
+    let result = process("2 * (1 + 0xFFFFFFFFFFFFFFFFFFFFFFFFFF)");
+    let expected = "2 * (1 + 0xFFFFFFFFFFFFFFFFFFFFFFFFFF)\n         ^\nError: Number literal out of range";
+    assert_eq!(result, expected);
+
+    let result = process("1e999999999");
+    let expected = "1e999999999\n^\nError: Number literal out of range";
+    assert_eq!(result, expected);
+
+    let result = process("precision 50; 1e-999999999");
+    let expected = "1e-999999999\n^\nError: Number literal out of range";
+    assert_eq!(result, expected);
+
+    let result = process("mode decimal; 1e-40");
+    let expected = "0";
+    assert_eq!(result, expected);
+
+    let result = process("1.2.3");
+    let expected = "1.2.3\n^\nError: Syntax error";
+    assert_eq!(result, expected);
+
+    let result = process("1.2.3 + 1");
+    let expected = "1.2.3 + 1\n^\nError: Syntax error";
+    assert_eq!(result, expected);
+
+    let result = process("1.5e2.3");
+    let expected = "1.5e2.3\n^\nError: Syntax error";
+    assert_eq!(result, expected);
+
+    let result = process("0b12");
+    let expected = "0b12\n^\nError: Syntax error";
+    assert_eq!(result, expected);
+
+    let result = process("0xFF.5");
+    let expected = "0xFF.5\n^\nError: Syntax error";
+    assert_eq!(result, expected);
+
+    let result = process("0o8");
+    let expected = "0o8\n^\nError: Syntax error";
+    assert_eq!(result, expected);
+
+    let result = process("0x10..0x12");
+    let expected = "[16, 17]";
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn keyword_boundaries() {
+    let _session = lock_session();
+
+    let keywords = [
+        "if", "then", "else", "while", "for", "in", "break", "fn", "return", "print", "not", "and", "or", "pi",
+        "sin", "sind", "asin", "asind", "cos", "cosd", "acos", "acosd", "tan", "tand", "atan",
+        "atand", "ln", "log", "abs",
+    ];
+
+    for keyword in keywords {
+        let input = format!("{keyword}x = 1; x{keyword} = 2; {keyword}_{keyword} = 3");
+        let result = process(&input);
+        let expected = "3";
+        assert_eq!(result, expected, "{input}");
+
+        let input = format!("{keyword}x + x{keyword} + {keyword}_{keyword}");
+        let result = process(&input);
+        let expected = "6";
+        assert_eq!(result, expected, "{input}");
+
+        let input = format!("fn {keyword}1({keyword}2) {{ return {keyword}2 * 2 }}\n{keyword}1(4)");
+        let result = process(&input);
+        let expected = "8";
+        assert_eq!(result, expected, "{input}");
+    }
+
+    let result = process("origin = 1; cost = 2; android = 3; absolute = 4");
+    let expected = "4";
+    assert_eq!(result, expected);
+
+    let result = process("logistic = 5; printer = 6; piano = 7; notes = 8");
+    let expected = "8";
+    assert_eq!(result, expected);
+
+    let result = process("origin + cost + android + absolute + logistic + printer + piano + notes");
+    let expected = "36";
+    assert_eq!(result, expected);
+
+    let result = process("fn format(x) { return x + 1 }\nformat(1)");
+    let expected = "2";
+    assert_eq!(result, expected);
+
+    let result = process("thence = 1; if thence then thence else 0");
+    let expected = "1";
+    assert_eq!(result, expected);
+
+    let result = process("x = 0; if 1 { x = 1 } elsewhere = 2; x + elsewhere");
+    let expected = "3";
+    assert_eq!(result, expected);
+
+    let result = process("breaking = 0; while breaking < 3 { breaking = breaking + 1 } breaking");
+    let expected = "3";
+    assert_eq!(result, expected);
+
+    // keywords still work right before other tokens
+    let result = process("sin(pi)+cos(0)");
+    let expected = "1";
+    assert_eq!(result, expected);
+
+    let result = process("not(0)and(1)or(0)");
+    let expected = "1";
+    assert_eq!(result, expected);
+
+    let result = process("2pi");
+    let expected = "6.283185";
+    assert_eq!(result, expected);
+
+    let result = process("2piz");
+    let expected = "2piz\n ^\nError: Identifier not found";
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn precedence() {
+    let _session = lock_session();
+
+    let result = process("2^3^2");
+    let expected = "512";
+    assert_eq!(result, expected);
+
+    let result = process("-2^2");
+    let expected = "-4";
+    assert_eq!(result, expected);
+
+    let result = process("2^-1");
+    let expected = "0.5";
+    assert_eq!(result, expected);
+
+    let result = process("2^-1^2");
+    let expected = "0.5";
+    assert_eq!(result, expected);
+
+    let result = process("-2^-2");
+    let expected = "-0.25";
+    assert_eq!(result, expected);
+
+    let result = process("--2^2");
+    let expected = "4";
+    assert_eq!(result, expected);
+
+    let result = process("-3*2 + -2^2*3");
+    let expected = "-18";
+    assert_eq!(result, expected);
+
+    let result = process("x = 3; -2x^2");
+    let expected = "-18";
+    assert_eq!(result, expected);
+
+    // binary operators from the loosest to the tightest binding, only '^' is right-associative
+    let levels: [&[&str]; 5] = [
+        &["==", "!=", "<", ">"],
+        &["and", "or"],
+        &["+", "-"],
+        &["*", "/", "%"],
+        &["^"],
+    ];
+    let level = |op: &str| levels.iter().position(|ops| ops.contains(&op)).unwrap();
+    let operators = levels.concat();
+
+    for lhs in &operators {
+        for rhs in &operators {
+            let input = format!("5 {lhs} 3 {rhs} 2");
+            let grouped = if level(lhs) > level(rhs) || (level(lhs) == level(rhs) && *rhs != "^") {
+                format!("(5 {lhs} 3) {rhs} 2")
+            } else {
+                format!("5 {lhs} (3 {rhs} 2)")
+            };
+            assert_eq!(process(&input), process(&grouped), "{input}");
+        }
+
+        // unary minus binds tighter than everything but '^'
+        let input = format!("-5 {lhs} 3");
+        let grouped = if *lhs == "^" {
+            format!("-(5 {lhs} 3)")
+        } else {
+            format!("(-5) {lhs} 3")
+        };
+        assert_eq!(process(&input), process(&grouped), "{input}");
+
+        let input = format!("5 {lhs} -3 ^ 2");
+        let grouped = format!("5 {lhs} (-(3 ^ 2))");
+        assert_eq!(process(&input), process(&grouped), "{input}");
+
+        // 'not' and the built-in functions bind tighter than '^'
+        let input = format!("not 5 {lhs} 3");
+        let grouped = format!("(not 5) {lhs} 3");
+        assert_eq!(process(&input), process(&grouped), "{input}");
+
+        let input = format!("abs -5 {lhs} 3");
+        let grouped = format!("(abs -5) {lhs} 3");
+        assert_eq!(process(&input), process(&grouped), "{input}");
+    }
+
+    // assignment binds the loosest and is right-associative
+    let result = process("x = y = 2 + 3 * 4 ^ 2 > 1; x + y");
+    let expected = "2";
+    assert_eq!(result, expected);
+
+    crate::set_legacy_precedence(true);
+
+    let result = process("2^3^2");
+    let expected = "64";
+    assert_eq!(result, expected);
+
+    let result = process("-2^2");
+    let expected = "4";
+    assert_eq!(result, expected);
+
+    let result = process("2 + -2 ^ 8");
+    let expected = "258";
+    assert_eq!(result, expected);
+
+    let result = process("2^-(1)*3");
+    let expected = "1.5";
+    assert_eq!(result, expected);
+
+    crate::set_legacy_precedence(false);
+
+    let result = process("-2^2");
+    let expected = "-4";
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn builtin_functions() {
+    let _session = lock_session();
+
+    let result = process("sin(pi/2) + sin (pi/2) + sin pi/2");
+    let expected = "2";
+    assert_eq!(result, expected);
+
+    let result = process("sin cos 0 == sin(cos(0))");
+    let expected = "1";
+    assert_eq!(result, expected);
+
+    let result = process("x = pi/4; sin 2x");
+    let expected = "1";
+    assert_eq!(result, expected);
+
+    let result = process("2abs -3");
+    let expected = "6";
+    assert_eq!(result, expected);
+
+    let result = process("1 + tan(pi/2)");
+    let expected = "1 + tan(pi/2)\n    ^\nError: Argument outside of the function's domain";
+    assert_eq!(result, expected);
+
+    let result = process("sin(1, 2)");
+    let expected = "sin(1, 2)\n^\nError: Invalid number of arguments passed to function";
+    assert_eq!(result, expected);
+
+    // functions named without a call are values
+    let result = process("sin");
+    let expected = "sin";
+    assert_eq!(result, expected);
+
+    // variables don't hide built-in functions
+    let result = process("sin = 3; sin + sin(0)");
+    let expected = "3";
+    assert_eq!(result, expected);
+
+    // user defined functions do
+    let result = process("fn abs(x) { return x * 2 }\nabs(-4) + abs 1");
+    let expected = "-6";
+    assert_eq!(result, expected);
+
+    reset_session();
+    let result = process("abs(-4)");
+    let expected = "4";
+    assert_eq!(result, expected);
+
+    let builtins = crate::builtin_functions();
+    for (i, builtin) in builtins.iter().enumerate() {
+        assert!(!builtin.doc().is_empty(), "{}", builtin.name());
+        assert!(
+            builtins[i + 1..].iter().all(|other| other.name() != builtin.name()),
+            "{}",
+            builtin.name()
+        );
+    }
+}
+
+#[test]
+fn math_functions() {
+    let _session = lock_session();
+
+    let result = process("sqrt(2)");
+    let expected = "1.414214";
+    assert_eq!(result, expected);
+
+    let result = process("sqrt(16)");
+    let expected = "4";
+    assert_eq!(result, expected);
+
+    let result = process("cbrt(27)");
+    let expected = "3";
+    assert_eq!(result, expected);
+
+    let result = process("cbrt(-8)");
+    let expected = "-2";
+    assert_eq!(result, expected);
+
+    let result = process("cbrt(2)^3");
+    let expected = "2";
+    assert_eq!(result, expected);
+
+    let result = process("exp(1)");
+    let expected = "2.718282";
+    assert_eq!(result, expected);
+
+    let result = process("exp(0)");
+    let expected = "1";
+    assert_eq!(result, expected);
+
+    let result = process("exp(-1)");
+    let expected = "0.367879";
+    assert_eq!(result, expected);
+
+    let result = process("ln(exp(50))");
+    let expected = "50";
+    assert_eq!(result, expected);
+
+    let result = process("exp(-100)");
+    let expected = "0";
+    assert_eq!(result, expected);
+
+    let result = process("log(8, 2)");
+    let expected = "3";
+    assert_eq!(result, expected);
+
+    let result = process("log(1000)");
+    let expected = "3";
+    assert_eq!(result, expected);
+
+    let result = process("log(81, 3)");
+    let expected = "4";
+    assert_eq!(result, expected);
+
+    let result = process("log2(1024)");
+    let expected = "10";
+    assert_eq!(result, expected);
+
+    let result = process("floor(-2.5)");
+    let expected = "-3";
+    assert_eq!(result, expected);
+
+    let result = process("ceil(-2.5)");
+    let expected = "-2";
+    assert_eq!(result, expected);
+
+    let result = process("floor(2)");
+    let expected = "2";
+    assert_eq!(result, expected);
+
+    let result = process("round(2.5)");
+    let expected = "3";
+    assert_eq!(result, expected);
+
+    let result = process("round(-2.5)");
+    let expected = "-3";
+    assert_eq!(result, expected);
+
+    let result = process("round(3.14159, 2)");
+    let expected = "3.14";
+    assert_eq!(result, expected);
+
+    let result = process("round(2.675, 2)");
+    let expected = "2.68";
+    assert_eq!(result, expected);
+
+    let result = process("trunc(-2.7)");
+    let expected = "-2";
+    assert_eq!(result, expected);
+
+    let result = process("sign(-3)");
+    let expected = "-1";
+    assert_eq!(result, expected);
+
+    let result = process("sign(0)");
+    let expected = "0";
+    assert_eq!(result, expected);
+
+    let result = process("sign(0.5)");
+    let expected = "1";
+    assert_eq!(result, expected);
+
+    let result = process("min(3, 1, 2)");
+    let expected = "1";
+    assert_eq!(result, expected);
+
+    let result = process("max(3, 1, 2)");
+    let expected = "3";
+    assert_eq!(result, expected);
+
+    let result = process("min(-4)");
+    let expected = "-4";
+    assert_eq!(result, expected);
+
+    let result = process("hypot(3, 4)");
+    let expected = "5";
+    assert_eq!(result, expected);
+
+    let result = process("hypot(-5, 12)");
+    let expected = "13";
+    assert_eq!(result, expected);
+
+    let result = process("hypot(0, 0)");
+    let expected = "0";
+    assert_eq!(result, expected);
+
+    let result = process("atan2(1, 1)");
+    let expected = "0.785398";
+    assert_eq!(result, expected);
+
+    let result = process("atan2(1, -1)");
+    let expected = "2.356194";
+    assert_eq!(result, expected);
+
+    let result = process("atan2(-1, -1)");
+    let expected = "-2.356194";
+    assert_eq!(result, expected);
+
+    let result = process("atan2(1, 0)");
+    let expected = "1.570796";
+    assert_eq!(result, expected);
+
+    let result = process("atan2(0, 0)");
+    let expected = "0";
+    assert_eq!(result, expected);
+
+    let result = process("sinh(1)");
+    let expected = "1.175201";
+    assert_eq!(result, expected);
+
+    let result = process("cosh(1)");
+    let expected = "1.543081";
+    assert_eq!(result, expected);
+
+    let result = process("tanh(1)");
+    let expected = "0.761594";
+    assert_eq!(result, expected);
+
+    let result = process("tanh(-100)");
+    let expected = "-1";
+    assert_eq!(result, expected);
+
+    let result = process("tanh(35)");
+    let expected = "1";
+    assert_eq!(result, expected);
+
+    let result = process("asinh(sinh(2))");
+    let expected = "2";
+    assert_eq!(result, expected);
+
+    let result = process("asinh(-1)");
+    let expected = "-0.881374";
+    assert_eq!(result, expected);
+
+    let result = process("asinh(-1e15)");
+    let expected = "-35.231924";
+    assert_eq!(result, expected);
+
+    let result = process("acosh(cosh(2))");
+    let expected = "2";
+    assert_eq!(result, expected);
+
+    let result = process("acosh(1)");
+    let expected = "0";
+    assert_eq!(result, expected);
+
+    let result = process("acosh(1e15)");
+    let expected = "35.231924";
+    assert_eq!(result, expected);
+
+    let result = process("atanh(tanh(0.5))");
+    let expected = "0.5";
+    assert_eq!(result, expected);
+
+    let result = process("sqrt(-1)");
+    let expected = "sqrt(-1)\n^\nError: Argument outside of the function's domain";
+    assert_eq!(result, expected);
+
+    let result = process("sqrt(-4)");
+    let expected = "sqrt(-4)\n^\nError: Argument outside of the function's domain";
+    assert_eq!(result, expected);
+
+    let result = process("ln(-1)");
+    let expected = "ln(-1)\n^\nError: Argument outside of the function's domain";
+    assert_eq!(result, expected);
+
+    let result = process("log(8, 1)");
+    let expected = "log(8, 1)\n^\nError: Argument outside of the function's domain";
+    assert_eq!(result, expected);
+
+    let result = process("log(5, 1)");
+    let expected = "log(5, 1)\n^\nError: Argument outside of the function's domain";
+    assert_eq!(result, expected);
+
+    let result = process("log(5, 0)");
+    let expected = "log(5, 0)\n^\nError: Argument outside of the function's domain";
+    assert_eq!(result, expected);
+
+    let result = process("log(0, 2)");
+    let expected = "log(0, 2)\n^\nError: Argument outside of the function's domain";
+    assert_eq!(result, expected);
+
+    let result = process("round(1, -1)");
+    let expected = "round(1, -1)\n^\nError: Argument outside of the function's domain";
+    assert_eq!(result, expected);
+
+    let result = process("round(1, 1.5)");
+    let expected = "round(1, 1.5)\n^\nError: Argument outside of the function's domain";
+    assert_eq!(result, expected);
+
+    let result = process("exp(70)");
+    let expected = "exp(70)\n^\nError: Math error";
+    assert_eq!(result, expected);
+
+    let result = process("cosh(100)");
+    let expected = "cosh(100)\n^\nError: Math error";
+    assert_eq!(result, expected);
+
+    let result = process("acosh(0.5)");
+    let expected = "acosh(0.5)\n^\nError: Argument outside of the function's domain";
+    assert_eq!(result, expected);
+
+    let result = process("atanh(1)");
+    let expected = "atanh(1)\n^\nError: Argument outside of the function's domain";
+    assert_eq!(result, expected);
+
+    let result = process("atanh(-2)");
+    let expected = "atanh(-2)\n^\nError: Argument outside of the function's domain";
+    assert_eq!(result, expected);
+
+    let result = process("min()");
+    let expected = "min()\n^\nError: Invalid number of arguments passed to function";
+    assert_eq!(result, expected);
+
+    let result = process("hypot(1)");
+    let expected = "hypot(1)\n^\nError: Invalid number of arguments passed to function";
+    assert_eq!(result, expected);
+
+    let result = process("log(1, 2, 3)");
+    let expected = "log(1, 2, 3)\n^\nError: Invalid number of arguments passed to function";
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn inverse_trigonometry() {
+    let _session = lock_session();
+
+    let result = process("asin(0.5)");
+    let expected = "0.523599";
+    assert_eq!(result, expected);
+
+    let result = process("asin(-1)");
+    let expected = "-1.570796";
+    assert_eq!(result, expected);
+
+    let result = process("acos(0.5)");
+    let expected = "1.047198";
+    assert_eq!(result, expected);
+
+    let result = process("acos(-1)");
+    let expected = "3.141593";
+    assert_eq!(result, expected);
+
+    let result = process("acos(1)");
+    let expected = "0";
+    assert_eq!(result, expected);
+
+    let result = process("atan(1)");
+    let expected = "0.785398";
+    assert_eq!(result, expected);
+
+    let result = process("atan(-1000)");
+    let expected = "-1.569796";
+    assert_eq!(result, expected);
+
+    let result = process("asind(0.5)");
+    let expected = "30";
+    assert_eq!(result, expected);
+
+    let result = process("acosd(-0.5)");
+    let expected = "120";
+    assert_eq!(result, expected);
+
+    let result = process("atand(-1)");
+    let expected = "-45";
+    assert_eq!(result, expected);
+
+    // the results are exact to the last of the 28 digits
+    let result = process("asin(1) == pi / 2");
+    let expected = "1";
+    assert_eq!(result, expected);
+
+    let result = process("4 * atan(1) - pi < 1e-27");
+    let expected = "1";
+    assert_eq!(result, expected);
+
+    let result = process("x = 0.3; abs(sin(asin(x)) - x) < 1e-27");
+    let expected = "1";
+    assert_eq!(result, expected);
+
+    let result = process("x = -0.7; abs(sin(asin(x)) - x) < 1e-27");
+    let expected = "1";
+    assert_eq!(result, expected);
+
+    let result = process("x = 0.99; abs(sin(asin(x)) - x) < 1e-27");
+    let expected = "1";
+    assert_eq!(result, expected);
+
+    let result = process("x = 0.123456789; abs(cos(acos(x)) - x) < 1e-27");
+    let expected = "1";
+    assert_eq!(result, expected);
+
+    let result = process("x = -0.5; abs(cos(acos(x)) - x) < 1e-27");
+    let expected = "1";
+    assert_eq!(result, expected);
+
+    let result = process("x = 0.5; abs(tan(atan(x)) - x) < 1e-27");
+    let expected = "1";
+    assert_eq!(result, expected);
+
+    let result = process("x = 0.6; abs(asin(x) + acos(x) - pi / 2) < 1e-27");
+    let expected = "1";
+    assert_eq!(result, expected);
+
+    let result = process("asin(1.0001)");
+    let expected = "asin(1.0001)\n^\nError: Argument outside of the function's domain";
+    assert_eq!(result, expected);
+
+    let result = process("asin(-2)");
+    let expected = "asin(-2)\n^\nError: Argument outside of the function's domain";
+    assert_eq!(result, expected);
+
+    let result = process("acos(2)");
+    let expected = "acos(2)\n^\nError: Argument outside of the function's domain";
+    assert_eq!(result, expected);
+
+    let result = process("asind(-1.5)");
+    let expected = "asind(-1.5)\n^\nError: Argument outside of the function's domain";
+    assert_eq!(result, expected);
+
+    let result = process("acosd(1.5)");
+    let expected = "acosd(1.5)\n^\nError: Argument outside of the function's domain";
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn angle_mode() {
+    let _session = lock_session();
+
+    let result = process("mode deg");
+    let expected = "";
+    assert_eq!(result, expected);
+
+    let result = process("sin 30");
+    let expected = "0.5";
+    assert_eq!(result, expected);
+
+    let result = process("cos(180)");
+    let expected = "-1";
+    assert_eq!(result, expected);
+
+    let result = process("tan 45");
+    let expected = "1";
+    assert_eq!(result, expected);
+
+    let result = process("asin(1)");
+    let expected = "90";
+    assert_eq!(result, expected);
+
+    let result = process("acos(0.5)");
+    let expected = "60";
+    assert_eq!(result, expected);
+
+    let result = process("atan2(1, 1)");
+    let expected = "45";
+    assert_eq!(result, expected);
+
+    let result = process("sind 90");
+    let expected = "1";
+    assert_eq!(result, expected);
+
+    let result = process("deg(pi)");
+    let expected = "180";
+    assert_eq!(result, expected);
+
+    let result = process("rad(180)");
+    let expected = "3.141593";
+    assert_eq!(result, expected);
+
+    let result = process("mode grad; sin(100)");
+    let expected = "1";
+    assert_eq!(result, expected);
+
+    let result = process("tan(50)");
+    let expected = "1";
+    assert_eq!(result, expected);
+
+    let result = process("acos(0)");
+    let expected = "100";
+    assert_eq!(result, expected);
+
+    let result = process("sind 90");
+    let expected = "1";
+    assert_eq!(result, expected);
+
+    let result = process("mode rad\nsin(pi / 2)");
+    let expected = "1";
+    assert_eq!(result, expected);
+
+    let result = process("asin(1)");
+    let expected = "1.570796";
+    assert_eq!(result, expected);
+
+    let result = process("fn f(x) {\n mode deg\n return sin x\n}\nf(90)");
+    let expected = "1";
+    assert_eq!(result, expected);
+
+    let result = process("cos 60");
+    let expected = "0.5";
+    assert_eq!(result, expected);
+
+    let result = process("mode = 3; mode * 2");
+    let expected = "6";
+    assert_eq!(result, expected);
+
+    crate::set_angle_mode(crate::AngleMode::Gradians);
+    let result = process("tan(100)");
+    let expected = "tan(100)\n^\nError: Argument outside of the function's domain";
+    assert_eq!(result, expected);
+
+    let result = process("tan(-300)");
+    let expected = "tan(-300)\n^\nError: Argument outside of the function's domain";
+    assert_eq!(result, expected);
+
+    let result = process("mode deg; tan(90)");
+    let expected = "tan(90)\n^\nError: Argument outside of the function's domain";
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn complex_numbers() {
+    let _session = lock_session();
+
+    let result = process("sqrt(-1)");
+    let expected = "sqrt(-1)\n^\nError: Argument outside of the function's domain";
+    assert_eq!(result, expected);
+
+    let result = process("2i");
+    let expected = "2i\n ^\nError: Identifier not found";
+    assert_eq!(result, expected);
+
+    crate::set_complex_numbers(true);
+    let result = process("i^2");
+    let expected = "-1";
+    assert_eq!(result, expected);
+
+    let result = process("sqrt(-1)");
+    let expected = "i";
+    assert_eq!(result, expected);
+
+    let result = process("sqrt(-4)");
+    let expected = "2i";
+    assert_eq!(result, expected);
+
+    let result = process("3 + 4i");
+    let expected = "3 + 4i";
+    assert_eq!(result, expected);
+
+    let result = process("1 - i");
+    let expected = "1 - i";
+    assert_eq!(result, expected);
+
+    let result = process("-2.5i");
+    let expected = "-2.5i";
+    assert_eq!(result, expected);
+
+    let result = process("(3 + 4i)(3 - 4i)");
+    let expected = "25";
+    assert_eq!(result, expected);
+
+    let result = process("(1 + 2i) / (3 - 4i)");
+    let expected = "-0.2 + 0.4i";
+    assert_eq!(result, expected);
+
+    let result = process("x = 2 + 3i; x * x");
+    let expected = "-5 + 12i";
+    assert_eq!(result, expected);
+
+    let result = process("abs(3 + 4i)");
+    let expected = "5";
+    assert_eq!(result, expected);
+
+    let result = process("re(3 + 4i)");
+    let expected = "3";
+    assert_eq!(result, expected);
+
+    let result = process("im(3 + 4i)");
+    let expected = "4";
+    assert_eq!(result, expected);
+
+    let result = process("conj(3 + 4i)");
+    let expected = "3 - 4i";
+    assert_eq!(result, expected);
+
+    let result = process("arg(i)");
+    let expected = "1.570796";
+    assert_eq!(result, expected);
+
+    let result = process("arg(-1)");
+    let expected = "3.141593";
+    assert_eq!(result, expected);
+
+    let result = process("sqrt(3 + 4i)");
+    let expected = "2 + i";
+    assert_eq!(result, expected);
+
+    let result = process("sqrt(-3 - 4i)");
+    let expected = "1 - 2i";
+    assert_eq!(result, expected);
+
+    let result = process("ln(-1)");
+    let expected = "3.141593i";
+    assert_eq!(result, expected);
+
+    let result = process("exp(i * pi)");
+    let expected = "-1";
+    assert_eq!(result, expected);
+
+    let result = process("exp(1 + i)");
+    let expected = "1.468694 + 2.287355i";
+    assert_eq!(result, expected);
+
+    let result = process("log(-100)");
+    let expected = "2 + 1.364376i";
+    assert_eq!(result, expected);
+
+    let result = process("(-8)^(1/3)");
+    let expected = "1 + 1.732051i";
+    assert_eq!(result, expected);
+
+    let result = process("(-8)^3");
+    let expected = "-512";
+    assert_eq!(result, expected);
+
+    let result = process("i^i");
+    let expected = "0.20788";
+    assert_eq!(result, expected);
+
+    let result = process("(1 + i)^-2");
+    let expected = "-0.5i";
+    assert_eq!(result, expected);
+
+    let result = process("sin(i)");
+    let expected = "1.175201i";
+    assert_eq!(result, expected);
+
+    let result = process("cos(i)");
+    let expected = "1.543081";
+    assert_eq!(result, expected);
+
+    let result = process("tan(1 + i)");
+    let expected = "0.271753 + 1.083923i";
+    assert_eq!(result, expected);
+
+    let result = process("2i == 2 * i");
+    let expected = "1";
+    assert_eq!(result, expected);
+
+    let result = process("if i then 1 else 0");
+    let expected = "1";
+    assert_eq!(result, expected);
+
+    let result = process("print 2i");
+    let expected = "2i";
+    assert_eq!(result, expected);
+
+    let result = process("i = 5; 2i");
+    let expected = "10";
+    assert_eq!(result, expected);
+    reset_session();
+
+    let result = process("i < 1");
+    let expected = "i < 1\n  ^\nError: Expected a real number";
+    assert_eq!(result, expected);
+
+    let result = process("(1 + i) % 2");
+    let expected = "(1 + i) % 2\n        ^\nError: Expected a real number";
+    assert_eq!(result, expected);
+
+    let result = process("floor(i)");
+    let expected = "floor(i)\n^\nError: Expected a real number";
+    assert_eq!(result, expected);
+
+    let result = process("0^i");
+    let expected = "0^i\n ^\nError: Invalid exponent";
+    assert_eq!(result, expected);
+
+    let result = process("1 / (i - i)");
+    let expected = "1 / (i - i)\n  ^\nError: Division by zero";
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn exact_fractions() {
+    let _session = lock_session();
+
+    let result = process("mode exact");
+    let expected = "";
+    assert_eq!(result, expected);
+
+    let result = process("1/3");
+    let expected = "1/3";
+    assert_eq!(result, expected);
+
+    let result = process("1/3*3");
+    let expected = "1";
+    assert_eq!(result, expected);
+
+    let result = process("5/3");
+    let expected = "1 2/3";
+    assert_eq!(result, expected);
+
+    let result = process("-5/3");
+    let expected = "-1 2/3";
+    assert_eq!(result, expected);
+
+    let result = process("-1/3");
+    let expected = "-1/3";
+    assert_eq!(result, expected);
+
+    let result = process("0.25 + 1/4");
+    let expected = "1/2";
+    assert_eq!(result, expected);
+
+    let result = process("1e-3");
+    let expected = "1/1000";
+    assert_eq!(result, expected);
+
+    let result = process("0x10 / 6");
+    let expected = "2 2/3";
+    assert_eq!(result, expected);
+
+    let result = process("(2/3)^2");
+    let expected = "4/9";
+    assert_eq!(result, expected);
+
+    let result = process("(2/3)^-2");
+    let expected = "2 1/4";
+    assert_eq!(result, expected);
+
+    let result = process("7/2 % 1");
+    let expected = "1/2";
+    assert_eq!(result, expected);
+
+    let result = process("-7/2 % 1");
+    let expected = "-1/2";
+    assert_eq!(result, expected);
+
+    let result = process("1/3 == 2/6");
+    let expected = "1";
+    assert_eq!(result, expected);
+
+    let result = process("1/3 < 0.34");
+    let expected = "1";
+    assert_eq!(result, expected);
+
+    let result = process("(1 < 2) + 1/2");
+    let expected = "1 1/2";
+    assert_eq!(result, expected);
+
+    let result = process("abs(-2/3)");
+    let expected = "2/3";
+    assert_eq!(result, expected);
+
+    let result = process("x = 1/3; print x; print x + 1");
+    let expected = "1/3\n1 1/3";
+    assert_eq!(result, expected);
+
+    let result = process("fn half(x) { return x / 2 }\nhalf(1/3)");
+    let expected = "1/6";
+    assert_eq!(result, expected);
+
+    let result = process("x = 1/3^40; x * 3^40");
+    let expected = "1";
+    assert_eq!(result, expected);
+
+    let result = process("min(1/3, 1/4)");
+    let expected = "1/4";
+    assert_eq!(result, expected);
+
+    let result = process("max([1/3, 1/4])");
+    let expected = "1/3";
+    assert_eq!(result, expected);
+
+    let result = process("(-1)^1000000000001");
+    let expected = "-1";
+    assert_eq!(result, expected);
+
+    let result = process("(1/7)^100");
+    let expected = "(1/7)^100\n     ^\nError: Value out of range";
+    assert_eq!(result, expected);
+
+    // transcendental functions and constants fall back to decimal numbers
+    let result = process("2^0.5");
+    let expected = "1.414214";
+    assert_eq!(result, expected);
+
+    let result = process("sqrt(4/9)");
+    let expected = "0.666667";
+    assert_eq!(result, expected);
+
+    let result = process("1/3 + pi");
+    let expected = "3.474926";
+    assert_eq!(result, expected);
+
+    let result = process("1/3^60 + 1/2^60 > 0");
+    let expected = "1";
+    assert_eq!(result, expected);
+
+    let result = process("mode decimal; 1/3");
+    let expected = "0.333333";
+    assert_eq!(result, expected);
+
+    crate::set_number_mode(crate::NumberMode::Exact);
+    let result = process("1 % 0");
+    let expected = "1 % 0\n  ^\nError: Division by zero";
+    assert_eq!(result, expected);
+
+    let result = process("0^-1");
+    let expected = "0^-1\n ^\nError: Division by zero";
+    assert_eq!(result, expected);
+
+    let result = process("0^(-1/2)");
+    let expected = "0^(-1/2)\n ^\nError: Division by zero";
+    assert_eq!(result, expected);
+
+    let result = process("mode decimal; 0^-2");
+    let expected = "0^-2\n ^\nError: Division by zero";
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn precise_numbers() {
+    let _session = lock_session();
+
+    let result = process("10^28 * 100");
+    let expected = "10^28 * 100\n      ^\nError: Value out of range";
+    assert_eq!(result, expected);
+
+    let result = process("precision 50");
+    let expected = "";
+    assert_eq!(result, expected);
+
+    let result = process("2^100");
+    let expected = "1267650600228229401496703205376";
+    assert_eq!(result, expected);
+
+    let result = process("1/3");
+    let expected = "0.33333333333333333333333333333333333333333333333333";
+    assert_eq!(result, expected);
+
+    let result = process("1/7");
+    let expected = "0.14285714285714285714285714285714285714285714285714";
+    assert_eq!(result, expected);
+
+    let result = process("0.1 + 0.2");
+    let expected = "0.3";
+    assert_eq!(result, expected);
+
+    let result = process("2^200");
+    let expected = "1.6069380442589902755419620923411626025222029937828e60";
+    assert_eq!(result, expected);
+
+    let result = process("10^60");
+    let expected = "1e60";
+    assert_eq!(result, expected);
+
+    let result = process("1e-70 * 3");
+    let expected = "3e-70";
+    assert_eq!(result, expected);
+
+    let result = process("12345678901234567890123456789012345678901234567890 + 1");
+    let expected = "12345678901234567890123456789012345678901234567891";
+    assert_eq!(result, expected);
+
+    let result = process("floor(2^100 / 3)");
+    let expected = "422550200076076467165567735125";
+    assert_eq!(result, expected);
+
+    let result = process("2^100 % 7");
+    let expected = "2";
+    assert_eq!(result, expected);
+
+    let result = process("pi");
+    let expected = "3.1415926535897932384626433832795028841971693993751";
+    assert_eq!(result, expected);
+
+    let result = process("e");
+    let expected = "2.7182818284590452353602874713526624977572470937";
+    assert_eq!(result, expected);
+
+    let result = process("exp(1) == e");
+    let expected = "1";
+    assert_eq!(result, expected);
+
+    let result = process("atan(1) * 4 == pi");
+    let expected = "1";
+    assert_eq!(result, expected);
+
+    let result = process("sqrt(2)");
+    let expected = "1.4142135623730950488016887242096980785696718753769";
+    assert_eq!(result, expected);
+
+    let result = process("ln(2)");
+    let expected = "0.69314718055994530941723212145817656807550013436026";
+    assert_eq!(result, expected);
+
+    let result = process("sin(1)");
+    let expected = "0.84147098480789650665250232163029899962256306079837";
+    assert_eq!(result, expected);
+
+    let result = process("log(8, 2)");
+    let expected = "3";
+    assert_eq!(result, expected);
+
+    let result = process("fn fact(n) { if n < 2 { return 1 } return n * fact(n - 1) }\nfact(40)");
+    let expected = "815915283247897734345611269596115894272000000000";
+    assert_eq!(result, expected);
+
+    let result = process("precision = 3; precision * 2");
+    let expected = "6";
+    assert_eq!(result, expected);
+
+    let result = process("mode decimal; 1/3");
+    let expected = "0.333333";
+    assert_eq!(result, expected);
+
+    let result = process("precision 0");
+    let expected = "precision 0\n          ^\nError: Precision must be between 1 and 1000";
+    assert_eq!(result, expected);
+
+    let result = process("precision 5000");
+    let expected = "precision 5000\n          ^\nError: Precision must be between 1 and 1000";
+    assert_eq!(result, expected);
+
+    crate::set_number_mode(crate::NumberMode::Precise(30));
+    let result = process("2/3");
+    let expected = "0.666666666666666666666666666667";
+    assert_eq!(result, expected);
+
+    let result = process("tan(pi/2)");
+    let expected = "tan(pi/2)\n^\nError: Argument outside of the function's domain";
+    assert_eq!(result, expected);
+
+    let result = process("tan(3 * pi/2)");
+    let expected = "tan(3 * pi/2)\n^\nError: Argument outside of the function's domain";
+    assert_eq!(result, expected);
+
+    let result = process("tan(0)");
+    let expected = "0";
+    assert_eq!(result, expected);
+
+    // precise values stay precise, but are printed like decimal numbers again
+    let result = process("x = 1/3; mode decimal; x");
+    let expected = "0.333333";
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn float_numbers() {
+    let _session = lock_session();
+
+    let result = process("mode float");
+    let expected = "";
+    assert_eq!(result, expected);
+
+    let result = process("0.1 + 0.2");
+    let expected = "0.3";
+    assert_eq!(result, expected);
+
+    let result = process("0.1 + 0.2 == 0.3");
+    let expected = "0";
+    assert_eq!(result, expected);
+
+    // literals are rounded to the nearest f64
+    let result = process("1e300");
+    let expected = "1e300";
+    assert_eq!(result, expected);
+
+    let result = process("1.7976931348623157e308");
+    let expected = "1.7976931348623157e308";
+    assert_eq!(result, expected);
+
+    let result = process("1/0");
+    let expected = "inf";
+    assert_eq!(result, expected);
+
+    let result = process("-1/0");
+    let expected = "-inf";
+    assert_eq!(result, expected);
+
+    let result = process("0/0");
+    let expected = "NaN";
+    assert_eq!(result, expected);
+
+    let result = process("0/0 == 0/0");
+    let expected = "0";
+    assert_eq!(result, expected);
+
+    let result = process("1 < 0/0");
+    let expected = "0";
+    assert_eq!(result, expected);
+
+    let result = process("1 > 0/0");
+    let expected = "0";
+    assert_eq!(result, expected);
+
+    let result = process("5 % 0");
+    let expected = "NaN";
+    assert_eq!(result, expected);
+
+    let result = process("-7 % 3");
+    let expected = "-1";
+    assert_eq!(result, expected);
+
+    let result = process("sqrt(-1)");
+    let expected = "NaN";
+    assert_eq!(result, expected);
+
+    let result = process("ln(0)");
+    let expected = "-inf";
+    assert_eq!(result, expected);
+
+    let result = process("asin(2)");
+    let expected = "NaN";
+    assert_eq!(result, expected);
+
+    let result = process("2^1024");
+    let expected = "inf";
+    assert_eq!(result, expected);
+
+    let result = process("1e400");
+    let expected = "inf";
+    assert_eq!(result, expected);
+
+    let result = process("10^20");
+    let expected = "1e20";
+    assert_eq!(result, expected);
+
+    let result = process("pi");
+    let expected = "3.141593";
+    assert_eq!(result, expected);
+
+    let result = process("sin(pi)");
+    let expected = "0";
+    assert_eq!(result, expected);
+
+    let result = process("log(8, 2)");
+    let expected = "3";
+    assert_eq!(result, expected);
+
+    let result = process("log2(8)");
+    let expected = "3";
+    assert_eq!(result, expected);
+
+    let result = process("hypot(3, 4)");
+    let expected = "5";
+    assert_eq!(result, expected);
+
+    let result = process("round(pi, 3)");
+    let expected = "3.142";
+    assert_eq!(result, expected);
+
+    let result = process("floor(-2.5) + ceil(2.1)");
+    let expected = "0";
+    assert_eq!(result, expected);
+
+    let result = process("abs(-3)");
+    let expected = "3";
+    assert_eq!(result, expected);
+
+    let result = process("sign(0/0)");
+    let expected = "NaN";
+    assert_eq!(result, expected);
+
+    let result = process("max(1, 3, 2)");
+    let expected = "3";
+    assert_eq!(result, expected);
+
+    let result = process("fn f(n) { if n < 2 { return 1 } return n * f(n - 1) }\nf(171)");
+    let expected = "inf";
+    assert_eq!(result, expected);
+
+    let result = process("mode deg; asin(0.5)");
+    let expected = "30";
+    assert_eq!(result, expected);
+
+    let result = process("mode rad; mode decimal; 1/3");
+    let expected = "0.333333";
+    assert_eq!(result, expected);
+
+    let result = process("1/0");
+    let expected = "1/0\n ^\nError: Division by zero";
+    assert_eq!(result, expected);
+
+    crate::set_number_mode(crate::NumberMode::Float);
+    crate::set_complex_numbers(true);
+    let result = process("sqrt(-4)");
+    let expected = "2i";
+    assert_eq!(result, expected);
+
+    let result = process("i^2");
+    let expected = "-1";
+    assert_eq!(result, expected);
+
+    let result = process("(1/0) + i");
+    let expected = "(1/0) + i\n      ^\nError: Value out of range";
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn constants() {
+    let _session = lock_session();
+
+    let result = process("pi");
+    let expected = "3.141593";
+    assert_eq!(result, expected);
+
+    let result = process("e");
+    let expected = "2.718282";
+    assert_eq!(result, expected);
+
+    let result = process("tau");
+    let expected = "6.283185";
+    assert_eq!(result, expected);
+
+    let result = process("phi");
+    let expected = "1.618034";
+    assert_eq!(result, expected);
+
+    let result = process("tau == 2pi");
+    let expected = "1";
+    assert_eq!(result, expected);
+
+    let result = process("phi^2 - phi");
+    let expected = "1";
+    assert_eq!(result, expected);
+
+    let result = process("ln e");
+    let expected = "1";
+    assert_eq!(result, expected);
+
+    let result = process("2e");
+    let expected = "5.436564";
+    assert_eq!(result, expected);
+
+    let result = process("2e2");
+    let expected = "200";
+    assert_eq!(result, expected);
+
+    let result = process("e(2)");
+    let expected = "5.436564";
+    assert_eq!(result, expected);
+
+    let result = process("e = 3");
+    let expected = "e = 3\n  ^\nError: Invalid assignment";
+    assert_eq!(result, expected);
+
+    let result = process("x = pi = 3");
+    let expected = "x = pi = 3\n       ^\nError: Invalid assignment";
+    assert_eq!(result, expected);
+
+    // names of constants can't be bound in any other way either
+    let result = process("for e in [1, 2] { e }");
+    let expected = "for e in [1, 2] { e }\n    ^\nError: Invalid assignment";
+    assert_eq!(result, expected);
+
+    let result = process("fn f(x, pi = 2) { x }");
+    let expected = "fn f(x, pi = 2) { x }\n        ^\nError: Invalid assignment";
+    assert_eq!(result, expected);
+
+    let result = process("[e for e in 1..3]");
+    let expected = "e for e in 1..3\n      ^\nError: Invalid assignment";
+    assert_eq!(result, expected);
+
+    let result = process("map(tau -> tau + 1, [1])");
+    let expected = "tau -> tau + 1\n^\nError: Invalid assignment";
+    assert_eq!(result, expected);
+
+    let result = process("fn f() {\n    fn phi() { 1 }\n    return 2\n}");
+    let expected = "fn phi() { 1 }\n   ^\nError: Invalid assignment";
+    assert_eq!(result, expected);
+
+    // physical constants are opt-in
+    let result = process("c = 3; c + N_A");
+    let expected = "c + N_A\n    ^\nError: Identifier not found";
+    assert_eq!(result, expected);
+
+    reset_session();
+    crate::set_physical_constants(true);
+
+    let result = process("c");
+    let expected = "299792458";
+    assert_eq!(result, expected);
+
+    let result = process("N_A * e_charge");
+    let expected = "96485.332123";
+    assert_eq!(result, expected);
+
+    let result = process("G");
+    let expected = "0";
+    assert_eq!(result, expected);
+
+    let result = process("1/3 + G");
+    let expected = "0.333333";
+    assert_eq!(result, expected);
+
+    let result = process("sin(G)");
+    let expected = "0";
+    assert_eq!(result, expected);
+
+    let result = process("G * 1e11");
+    let expected = "6.6743";
+    assert_eq!(result, expected);
+
+    let result = process("c = 3");
+    let expected = "c = 3\n  ^\nError: Invalid assignment";
+    assert_eq!(result, expected);
+
+    let result = process("h");
+    let expected = "6.62607e-34";
+    assert_eq!(result, expected);
+
+    let result = process("2 * h");
+    let expected = "1.325214e-33";
+    assert_eq!(result, expected);
+
+    let result = process("k_B");
+    let expected = "1.380649e-23";
+    assert_eq!(result, expected);
+
+    let result = process("h * c / (h * c)");
+    let expected = "1";
+    assert_eq!(result, expected);
+
+    let result = process("h * c");
+    let expected = "1.986446e-25";
+    assert_eq!(result, expected);
+
+    let result = process("1/3 + h");
+    let expected = "0.333333";
+    assert_eq!(result, expected);
+
+    let constant = crate::constant("k_B").unwrap();
+    assert!(constant.is_physical());
+    assert_eq!(constant.literal(), "1.380649e-23");
+    assert!(crate::constants().iter().all(|c| !c.doc().is_empty()));
+
+    crate::set_physical_constants(false);
+    let result = process("c");
+    let expected = "c\n^\nError: Identifier not found";
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn bitwise_operators() {
+    let _session = lock_session();
+
+    let result = process("6 & 3");
+    let expected = "2";
+    assert_eq!(result, expected);
+
+    let result = process("6 | 3");
+    let expected = "7";
+    assert_eq!(result, expected);
+
+    let result = process("6 xor 3");
+    let expected = "5";
+    assert_eq!(result, expected);
+
+    let result = process("~5");
+    let expected = "-6";
+    assert_eq!(result, expected);
+
+    let result = process("1 << 10");
+    let expected = "1024";
+    assert_eq!(result, expected);
+
+    let result = process("-16 >> 2");
+    let expected = "-4";
+    assert_eq!(result, expected);
+
+    let result = process("1 >> 200");
+    let expected = "0";
+    assert_eq!(result, expected);
+
+    let result = process("7 // 2");
+    let expected = "3";
+    assert_eq!(result, expected);
+
+    let result = process("-7 // 2");
+    let expected = "-4";
+    assert_eq!(result, expected);
+
+    let result = process("7.5 // 2");
+    let expected = "3";
+    assert_eq!(result, expected);
+
+    let result = process("1 + 2 & 3");
+    let expected = "3";
+    assert_eq!(result, expected);
+
+    let result = process("1 | 2 == 3");
+    let expected = "1";
+    assert_eq!(result, expected);
+
+    let result = process("2 * 3 << 1");
+    let expected = "12";
+    assert_eq!(result, expected);
+
+    let result = process("~-1 # a comment");
+    let expected = "0";
+    assert_eq!(result, expected);
+
+    let result = process("xor = 1; xor xor 1 /* not a keyword */");
+    let expected = "0";
+    assert_eq!(result, expected);
+
+    let result = process("1.5 & 1");
+    let expected = "1.5 & 1\n    ^\nError: Math error";
+    assert_eq!(result, expected);
+
+    let result = process("1 << -1");
+    let expected = "1 << -1\n  ^\nError: Value out of range";
+    assert_eq!(result, expected);
+
+    let result = process("1 << 100");
+    let expected = "1 << 100\n  ^\nError: Value out of range";
+    assert_eq!(result, expected);
+
+    let result = process("1 // 0");
+    let expected = "1 // 0\n  ^\nError: Division by zero";
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn integer_mode() {
+    let _session = lock_session();
+
+    let result = process("mode u8");
+    let expected = "";
+    assert_eq!(result, expected);
+
+    let result = process("~0");
+    let expected = "255";
+    assert_eq!(result, expected);
+
+    let result = process("7 / 2");
+    let expected = "3";
+    assert_eq!(result, expected);
+
+    let result = process("2^7 + 127");
+    let expected = "255";
+    assert_eq!(result, expected);
+
+    let result = process("255 + 1");
+    let expected = "255 + 1\n    ^\nError: Value out of range";
+    assert_eq!(result, expected);
+
+    let result = process("0 - 1");
+    let expected = "0 - 1\n  ^\nError: Value out of range";
+    assert_eq!(result, expected);
+
+    let result = process("0.5");
+    let expected = "0.5\n^\nError: Number literal out of range";
+    assert_eq!(result, expected);
+
+    let result = process("sqrt(16) + 1");
+    let expected = "5";
+    assert_eq!(result, expected);
+
+    let result = process("sqrt(2)");
+    let expected = "1";
+    assert_eq!(result, expected);
+
+    let result = process("sqrt(15) + 1");
+    let expected = "4";
+    assert_eq!(result, expected);
+
+    let result = process("exp(6)");
+    let expected = "exp(6)\n^\nError: Value out of range";
+    assert_eq!(result, expected);
+
+    let result = process("3 > 2");
+    let expected = "1";
+    assert_eq!(result, expected);
+
+    let result = process("mode wrap; 255 + 1");
+    let expected = "0";
+    assert_eq!(result, expected);
+
+    let result = process("0 - 1");
+    let expected = "255";
+    assert_eq!(result, expected);
+
+    let result = process("16 * 16 + 5");
+    let expected = "5";
+    assert_eq!(result, expected);
+
+    let result = process("mode hex; 0xf0 >> 4 | 0x0f << 4");
+    let expected = "0xff";
+    assert_eq!(result, expected);
+
+    let result = process("mode bin; 5");
+    let expected = "0b101";
+    assert_eq!(result, expected);
+
+    let result = process("mode i8; mode hex; -1");
+    let expected = "0xff";
+    assert_eq!(result, expected);
+
+    let result = process("mode oct; 0 - 8");
+    let expected = "0o370";
+    assert_eq!(result, expected);
+
+    let result = process("mode dec; 127 + 1");
+    let expected = "-128";
+    assert_eq!(result, expected);
+
+    let result = process("mode checked; 127 + 1");
+    let expected = "127 + 1\n    ^\nError: Value out of range";
+    assert_eq!(result, expected);
+
+    let result = process("mode u64; 0xFFFF_FFFF_FFFF_FFFF");
+    let expected = "18446744073709551615";
+    assert_eq!(result, expected);
+
+    let result = process("mode i64; 0xFFFF_FFFF_FFFF_FFFF");
+    let expected = "-1";
+    assert_eq!(result, expected);
+
+    let result = process("-9223372036854775808");
+    let expected = "-9223372036854775808";
+    assert_eq!(result, expected);
+
+    let result = process("0x1_0000_0000_0000_0000");
+    let expected = "0x1_0000_0000_0000_0000\n^\nError: Number literal out of range";
+    assert_eq!(result, expected);
+
+    let result = process("mode i8; -128");
+    let expected = "-128";
+    assert_eq!(result, expected);
+
+    let result = process("2 * -128");
+    let expected = "2 * -128\n  ^\nError: Value out of range";
+    assert_eq!(result, expected);
+
+    let result = process("-129");
+    let expected = "-129\n ^\nError: Number literal out of range";
+    assert_eq!(result, expected);
+
+    let result = process("0x80 | 0b0111_1111");
+    let expected = "-1";
+    assert_eq!(result, expected);
+
+    let result = process("0x100");
+    let expected = "0x100\n^\nError: Number literal out of range";
+    assert_eq!(result, expected);
+
+    let result = process("1 << -1");
+    let expected = "1 << -1\n  ^\nError: Value out of range";
+    assert_eq!(result, expected);
+
+    let result = process("mode u8; 1 << -1");
+    let expected = "1 << -1\n     ^\nError: Value out of range";
+    assert_eq!(result, expected);
+
+    let result = process("mode i64; -7 // 2 + -7 % 2");
+    let expected = "-5";
+    assert_eq!(result, expected);
+
+    let result = process("mode decimal; 1/4");
+    let expected = "0.25";
+    assert_eq!(result, expected);
+
+    crate::set_number_mode(crate::NumberMode::Integer(crate::IntegerType::U32));
+    crate::set_overflow(crate::Overflow::Wrap);
+    crate::set_radix(crate::Radix::Hex);
+    let result = process("~0 + 2");
+    let expected = "0x1";
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn lists() {
+    let _session = lock_session();
+
+    let result = process("[1, 2, 3]");
+    let expected = "[1, 2, 3]";
+    assert_eq!(result, expected);
+
+    let result = process("[]");
+    let expected = "[]";
+    assert_eq!(result, expected);
+
+    let result = process("[1/3, [2]]");
+    let expected = "[0.333333, [2]]";
+    assert_eq!(result, expected);
+
+    let result = process("xs = [3, 1, 4, 1, 5]; xs[0] + xs[-1]");
+    let expected = "8";
+    assert_eq!(result, expected);
+
+    let result = process("xs[1:3]");
+    let expected = "[1, 4]";
+    assert_eq!(result, expected);
+
+    let result = process("xs[:2]");
+    let expected = "[3, 1]";
+    assert_eq!(result, expected);
+
+    let result = process("xs[-2:]");
+    let expected = "[1, 5]";
+    assert_eq!(result, expected);
+
+    let result = process("xs[3:1]");
+    let expected = "[]";
+    assert_eq!(result, expected);
+
+    let result = process("xs[1:100]");
+    let expected = "[1, 4, 1, 5]";
+    assert_eq!(result, expected);
+
+    let result = process("xs[1 + 1]");
+    let expected = "4";
+    assert_eq!(result, expected);
+
+    let result = process("2xs[2]");
+    let expected = "8";
+    assert_eq!(result, expected);
+
+    let result = process("[[1, 2], [3]][0][1]");
+    let expected = "2";
+    assert_eq!(result, expected);
+
+    let result = process("len(xs)");
+    let expected = "5";
+    assert_eq!(result, expected);
+
+    let result = process("append(xs, 9, 2)");
+    let expected = "[3, 1, 4, 1, 5, 9, 2]";
+    assert_eq!(result, expected);
+
+    let result = process("len(xs)");
+    let expected = "5";
+    assert_eq!(result, expected);
+
+    let result = process("sum(xs) + prod(xs)");
+    let expected = "74";
+    assert_eq!(result, expected);
+
+    let result = process("min(xs) + max(xs)");
+    let expected = "6";
+    assert_eq!(result, expected);
+
+    let result = process("sum(1, 2) + sum([]) + prod([])");
+    let expected = "4";
+    assert_eq!(result, expected);
+
+    let result = process("[1, 2] == [1, 2]");
+    let expected = "1";
+    assert_eq!(result, expected);
+
+    let result = process("([1, 2] != [1]) + ([1] != 1)");
+    let expected = "2";
+    assert_eq!(result, expected);
+
+    let result = process("if [] then 1 else 2");
+    let expected = "2";
+    assert_eq!(result, expected);
+
+    let result = process("fn reversed(ys) { if len(ys) < 2 { return ys } return append(reversed(ys[1:]), ys[0]) }\nreversed(xs)");
+    let expected = "[5, 1, 4, 1, 3]";
+    assert_eq!(result, expected);
+
+    let result = process("x = 1\n[2, 3]");
+    let expected = "[2, 3]";
+    assert_eq!(result, expected);
+
+    let result = process("xs[5]");
+    let expected = "xs[5]\n  ^\nError: Index out of range";
+    assert_eq!(result, expected);
+
+    let result = process("xs[-6]");
+    let expected = "xs[-6]\n  ^\nError: Index out of range";
+    assert_eq!(result, expected);
+
+    let result = process("xs[0.5]");
+    let expected = "xs[0.5]\n  ^\nError: Wrong type of value";
+    assert_eq!(result, expected);
+
+    let result = process("xs[0.5:2]");
+    let expected = "xs[0.5:2]\n  ^\nError: Wrong type of value";
+    assert_eq!(result, expected);
+
+    let result = process("precision 50; xs[10^40]");
+    let expected = "xs[10^40]\n  ^\nError: Index out of range";
+    assert_eq!(result, expected);
+    crate::set_number_mode(crate::NumberMode::Decimal);
+
+    let result = process("1[0]");
+    let expected = "1[0]\n ^\nError: Wrong type of value";
+    assert_eq!(result, expected);
+
+    let result = process("xs + 1");
+    let expected = "xs + 1\n   ^\nError: Wrong type of value";
+    assert_eq!(result, expected);
+
+    let result = process("-xs");
+    let expected = "-xs\n^\nError: Wrong type of value";
+    assert_eq!(result, expected);
+
+    let result = process("xs < [3]");
+    let expected = "xs < [3]\n   ^\nError: Wrong type of value";
+    assert_eq!(result, expected);
+
+    let result = process("sqrt(xs)");
+    let expected = "sqrt(xs)\n^\nError: Wrong type of value";
+    assert_eq!(result, expected);
+
+    let result = process("min([])");
+    let expected = "min([])\n^\nError: Argument outside of the function's domain";
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn for_loops() {
+    let _session = lock_session();
+
+    let result = process("1..5");
+    let expected = "[1, 2, 3, 4]";
+    assert_eq!(result, expected);
+
+    let result = process("5..1");
+    let expected = "[]";
+    assert_eq!(result, expected);
+
+    let result = process("n = 3; 0..n + 1");
+    let expected = "[0, 1, 2, 3]";
+    assert_eq!(result, expected);
+
+    let result = process("total = 0\nfor x in [1, 2, 3] { total = total + x }\ntotal");
+    let expected = "6";
+    assert_eq!(result, expected);
+
+    let result = process("for x in 1..100 { if x * x > 50 { break } }\nx");
+    let expected = "8";
+    assert_eq!(result, expected);
+
+    let result = process("for i in 1..4 { print i }");
+    let expected = "1\n2\n3";
+    assert_eq!(result, expected);
+
+    let result = process("for x in [] { print x }");
+    let expected = "";
+    assert_eq!(result, expected);
+
+    let result = process("fn first_above(xs, y) { for x in xs { if x > y { return x } } return 0 }\nfirst_above([1, 5, 3], 2)");
+    let expected = "5";
+    assert_eq!(result, expected);
+
+    let result = process("[x^2 for x in 1..10 if x % 2 == 0]");
+    let expected = "[4, 16, 36, 64]";
+    assert_eq!(result, expected);
+
+    let result = process("y = 8; [[x, y] for x in 1..3]");
+    let expected = "[[1, 8], [2, 8]]";
+    assert_eq!(result, expected);
+
+    let result = process("[0 for x in []]");
+    let expected = "[]";
+    assert_eq!(result, expected);
+
+    // the variable of a comprehension doesn't leak out of it
+    let result = process("x = 42; y = [x for x in 1..3]; x");
+    let expected = "42";
+    assert_eq!(result, expected);
+
+    let result = process("[z * 2 for z in [1, 2]]; z");
+    let expected = "z\n^\nError: Identifier not found";
+    assert_eq!(result, expected);
+
+    let result = process("fn f() { x = 1; ys = [x for x in [5]]; return x }\nf()");
+    let expected = "1";
+    assert_eq!(result, expected);
+
+    let result = process("mode exact; [x/2 for x in 1..4]");
+    let expected = "[1/2, 1, 1 1/2]";
+    assert_eq!(result, expected);
+
+    let result = process("for x in 5 { x }");
+    let expected = "for x in 5 { x }\n         ^\nError: Wrong type of value";
+    assert_eq!(result, expected);
+
+    let result = process("[x for x in 1]");
+    let expected = "[x for x in 1]\n^\nError: Wrong type of value";
+    assert_eq!(result, expected);
+
+    let result = process("1.5..3");
+    let expected = "1.5..3\n   ^\nError: Wrong type of value";
+    assert_eq!(result, expected);
+
+    let result = process("1..3.5");
+    let expected = "1..3.5\n ^\nError: Wrong type of value";
+    assert_eq!(result, expected);
+
+    let result = process("0..2000000");
+    let expected = "Error: Maximum iteration count reached";
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn strings() {
+    let _session = lock_session();
+
+    let result = process(r#""hello""#);
+    let expected = "hello";
+    assert_eq!(result, expected);
+
+    let result = process(r#""""#);
+    let expected = "";
+    assert_eq!(result, expected);
+
+    let result = process(r#"name = "world"; "hello, " + name + "!""#);
+    let expected = "hello, world!";
+    assert_eq!(result, expected);
+
+    let result = process(r#""say \"hi\"\\""#);
+    let expected = r#"say "hi"\"#;
+    assert_eq!(result, expected);
+
+    let result = process(r#""a\tb\nc""#);
+    let expected = "a\tb\nc";
+    assert_eq!(result, expected);
+
+    let result = process(r#""x = " + str(1/3)"#);
+    let expected = "x = 0.333333";
+    assert_eq!(result, expected);
+
+    let result = process(r#"str([1, "a"]) + str("b")"#);
+    let expected = r#"[1, "a"]b"#;
+    assert_eq!(result, expected);
+
+    let result = process(r#"["a", ["b"]]"#);
+    let expected = r#"["a", ["b"]]"#;
+    assert_eq!(result, expected);
+
+    let result = process(r#"len("héllo") + len("")"#);
+    let expected = "5";
+    assert_eq!(result, expected);
+
+    let result = process(r#"("abc" < "abd") + ("b" > "abc") + ("a" == "a") + ("a" != 1)"#);
+    let expected = "4";
+    assert_eq!(result, expected);
+
+    let result = process(r#"if "" then 1 else 2"#);
+    let expected = "2";
+    assert_eq!(result, expected);
+
+    let result = process("fn greet(name) { return \"hi \" + name }\ngreet(\"you\")");
+    let expected = "hi you";
+    assert_eq!(result, expected);
+
+    let result = process(r#"print "x =", 1/2, "and", [1, 2]"#);
+    let expected = "x = 0.5 and [1, 2]";
+    assert_eq!(result, expected);
+
+    let result = process(r#"for x in 1..3 { print "x:", x, [x, "x"] }"#);
+    let expected = "x: 1 [1, \"x\"]\nx: 2 [2, \"x\"]";
+    assert_eq!(result, expected);
+
+    let result = process(r#""a" + 1"#);
+    let expected = "\"a\" + 1\n    ^\nError: Wrong type of value";
+    assert_eq!(result, expected);
+
+    let result = process(r#""a" < 1"#);
+    let expected = "\"a\" < 1\n    ^\nError: Wrong type of value";
+    assert_eq!(result, expected);
+
+    let result = process(r#"-"a""#);
+    let expected = "-\"a\"\n^\nError: Wrong type of value";
+    assert_eq!(result, expected);
+
+    let result = process(r#"sqrt("4")"#);
+    let expected = "sqrt(\"4\")\n^\nError: Wrong type of value";
+    assert_eq!(result, expected);
+
+    let result = process(r#""abc"[0]"#);
+    let expected = "\"abc\"[0]\n     ^\nError: Wrong type of value";
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn format_strings() {
+    let _session = lock_session();
+
+    let result = process(r#"v = 12.3456; f"{v:.2f} m/s""#);
+    let expected = "12.35 m/s";
+    assert_eq!(result, expected);
+
+    let result = process(r#"f"{1/3} {1/3:.2f} {2:.3f} {0.125:.2f} {-0.125:.2f}""#);
+    let expected = "0.333333 0.33 2.000 0.13 -0.13";
+    assert_eq!(result, expected);
+
+    let result = process(r#"f"{12345.678:.3e} {255:x} {255:#x} {-5:b} {8:#o}""#);
+    let expected = "1.235e4 ff 0xff -101 0o10";
+    assert_eq!(result, expected);
+
+    let result = process(r#"f"[{7:4}] [{7:<4}] [{7:^5}] [{7:*>4}] [{-7:05}] [{7:+}] [{255:#06x}]""#);
+    let expected = "[   7] [7   ] [  7  ] [***7] [-0007] [+7] [0x00ff]";
+    assert_eq!(result, expected);
+
+    let result = process(r#"f"[{"ab":4}] [{"ab":>4}] [{[1]:-^7}]""#);
+    let expected = r#"[ab  ] [  ab] [--[1]--]"#;
+    assert_eq!(result, expected);
+
+    let result = process(r#"f"{{x}} = {1 + 1}, \"{"nested " + f"{2}"}\"""#);
+    let expected = r#"{x} = 2, "nested 2""#;
+    assert_eq!(result, expected);
+
+    let result = process(r#"x = 3; f"{x}" + "!""#);
+    let expected = "3!";
+    assert_eq!(result, expected);
+
+    let result = process(r#"for x in 1..3 { print f"x = {x:.1f}" }"#);
+    let expected = "x = 1.0\nx = 2.0";
+    assert_eq!(result, expected);
+
+    let result = process(r#"format(2/3, ".3f") + format("a", ">3")"#);
+    let expected = "0.667  a";
+    assert_eq!(result, expected);
+
+    let result = process("mode float; format(0.1, \".20f\")");
+    let expected = "0.10000000000000000555";
+    assert_eq!(result, expected);
+
+    let result = process("mode decimal; precision 30; format(1/3, \".20f\")");
+    let expected = "0.33333333333333333333";
+    assert_eq!(result, expected);
+
+    let result = process("mode decimal; 1");
+    let expected = "1";
+    assert_eq!(result, expected);
+
+    let result = process(r#"f"{1 +}""#);
+    let expected = "f\"{1 +}\"\n      ^\nError: Syntax error";
+    assert_eq!(result, expected);
+
+    let result = process(r#"f"{1} }""#);
+    let expected = "f\"{1} }\"\n      ^\nError: Syntax error";
+    assert_eq!(result, expected);
+
+    let result = process(r#"f"{255:#X}""#);
+    let expected = "f\"{255:#X}\"\n       ^\nError: Invalid format specification";
+    assert_eq!(result, expected);
+
+    let result = process(r#"1 + f"{2:.2q}""#);
+    let expected = "1 + f\"{2:.2q}\"\n         ^\nError: Invalid format specification";
+    assert_eq!(result, expected);
+
+    let result = process(r#"1 + f"ab{1.5:x}""#);
+    let expected = "1 + f\"ab{1.5:x}\"\n         ^\nError: Math error";
+    assert_eq!(result, expected);
+
+    let result = process(r#"f"{"a":.2f}""#);
+    let expected = "f\"{\"a\":.2f}\"\n   ^\nError: Wrong type of value";
+    assert_eq!(result, expected);
+
+    let result = process(r#"f"{y}""#);
+    let expected = "y\n^\nError: Identifier not found";
+    assert_eq!(result, expected);
+
+    let result = process(r#"format(1, "z")"#);
+    let expected = "format(1, \"z\")\n^\nError: Invalid format specification";
+    assert_eq!(result, expected);
+
+    let result = process(r#"format(1, 2)"#);
+    let expected = "format(1, 2)\n^\nError: Wrong type of value";
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn functions_as_values() {
+    let _session = lock_session();
+
+    let result = process("square = x -> x^2; square(3) + square(4)");
+    let expected = "25";
+    assert_eq!(result, expected);
+
+    let result = process("add = (x, y) -> x + y; add(1, 2)");
+    let expected = "3";
+    assert_eq!(result, expected);
+
+    let result = process("answer = () -> 42; answer()");
+    let expected = "42";
+    assert_eq!(result, expected);
+
+    let result = process("add");
+    let expected = "(x, y) -> x + y";
+    assert_eq!(result, expected);
+
+    let result = process("fn sum_of(f, a, b) { return sum(map(f, a..b + 1)) }\nsum_of(x -> x^2, 1, 3) + sum_of(abs, -2, 0)");
+    let expected = "17";
+    assert_eq!(result, expected);
+
+    let result = process("fn inc(x) { x + 1 }\nmap(inc, [1, 2])");
+    let expected = "[2, 3]";
+    assert_eq!(result, expected);
+
+    let result = process("[inc, sin, x -> 2x]");
+    let expected = "[inc, sin, x -> 2x]";
+    assert_eq!(result, expected);
+
+    // the argument of a call without parentheses is on the same line
+    let result = process("f = sin\ng = abs # the absolute value\nf(0) + g(-3)");
+    let expected = "3";
+    assert_eq!(result, expected);
+
+    let result = process("sin\t-pi / 2 + abs  -2");
+    let expected = "2";
+    assert_eq!(result, expected);
+
+    let result = process("h = sin; h(0) + cos(0)");
+    let expected = "1";
+    assert_eq!(result, expected);
+
+    let result = process("f = cos; fn f(x) { x }\nf(0)");
+    let expected = "1";
+    assert_eq!(result, expected);
+
+    let result = process("(inc == inc) + (sin == sin) + (sin != cos) + (square != (x -> x^2))");
+    let expected = "4";
+    assert_eq!(result, expected);
+
+    let result = process("map(x -> x * 10, 1..4)");
+    let expected = "[10, 20, 30]";
+    assert_eq!(result, expected);
+
+    let result = process("filter(x -> x % 2 == 0, 1..10)");
+    let expected = "[2, 4, 6, 8]";
+    assert_eq!(result, expected);
+
+    let result = process("reduce((a, b) -> a * b, 1..6)");
+    let expected = "120";
+    assert_eq!(result, expected);
+
+    let result = process("reduce((a, b) -> a + b, [], 10) + reduce(max, [3])");
+    let expected = "13";
+    assert_eq!(result, expected);
+
+    let result = process("apply(max, [3, 9, 2]) + apply(add, [1, 2])");
+    let expected = "12";
+    assert_eq!(result, expected);
+
+    let result = process("map(x -> print x, [1])");
+    let expected = "map(x -> print x, [1])\n         ^\nError: Syntax error";
+    assert_eq!(result, expected);
+
+    let result = process("fn show(x) { print x }\nmap(show, [1, 2])");
+    let expected = "map(show, [1, 2])\n^\nError: Function did not return a value";
+    assert_eq!(result, expected);
+
+    let result = process("square(1, 2)");
+    let expected = "square(1, 2)\n^\nError: Invalid number of arguments passed to function, expected 1";
+    assert_eq!(result, expected);
+
+    let result = process("map(square, [1], 2)");
+    let expected = "map(square, [1], 2)\n^\nError: Invalid number of arguments passed to function";
+    assert_eq!(result, expected);
+
+    let result = process("map(1, [1])");
+    let expected = "map(1, [1])\n^\nError: Wrong type of value";
+    assert_eq!(result, expected);
+
+    let result = process("filter(square, 1)");
+    let expected = "filter(square, 1)\n^\nError: Wrong type of value";
+    assert_eq!(result, expected);
+
+    let result = process("reduce(max, [])");
+    let expected = "reduce(max, [])\n^\nError: Argument outside of the function's domain";
+    assert_eq!(result, expected);
+
+    let result = process("sin + 1");
+    let expected = "sin + 1\n    ^\nError: Wrong type of value";
+    assert_eq!(result, expected);
+
+    let result = process("sqrt(sin)");
+    let expected = "sqrt(sin)\n^\nError: Wrong type of value";
+    assert_eq!(result, expected);
+
+    let result = process("map(x -> y, [1])");
+    let expected = "y\n^\nError: Identifier not found";
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn closures() {
+    let _session = lock_session();
+
+    let result = process("k = 2; f = x -> k * x; k = 3; f(1) + k");
+    let expected = "5";
+    assert_eq!(result, expected);
+
+    let result = process("fn adder(n) { return x -> x + n }\nadd5 = adder(5); add5(1)");
+    let expected = "6";
+    assert_eq!(result, expected);
+
+    let result = process("map(adder(10), [1, 2])");
+    let expected = "[11, 12]";
+    assert_eq!(result, expected);
+
+    let result = process("adder(2) == adder(2)");
+    let expected = "1";
+    assert_eq!(result, expected);
+
+    let result = process("adder(2) == adder(3)");
+    let expected = "0";
+    assert_eq!(result, expected);
+
+    let result = process("adder(1)");
+    let expected = "x -> x + n";
+    assert_eq!(result, expected);
+
+    let result = process("fn outer(a) {\nfn helper(x) { x + a }\nreturn helper(2)\n}\nouter(10)");
+    let expected = "12";
+    assert_eq!(result, expected);
+
+    let result = process("fn fib(n) {\nfn go(k) { if k < 2 { return k }\nreturn go(k - 1) + go(k - 2) }\nreturn go(n)\n}\nfib(10)");
+    let expected = "55";
+    assert_eq!(result, expected);
+
+    let result = process("fn later() {\nc = 1\ninc = () -> c + 1\nc = 10\nreturn inc() + c\n}\nlater()");
+    let expected = "12";
+    assert_eq!(result, expected);
+
+    let result = process("fn local() {\nfn helper() { 1 }\nreturn helper\n}\nlocal()");
+    let expected = "helper";
+    assert_eq!(result, expected);
+
+    let result = process("h = local(); h()");
+    let expected = "1";
+    assert_eq!(result, expected);
+
+    let result = process("fn assign() { k = 100\nreturn k }\nassign() + k");
+    let expected = "103";
+    assert_eq!(result, expected);
+
+    let result = process("fn nested(a) {\nfn inner(b) { return () -> a + b }\nreturn inner(2)\n}\ng = nested(1); g()");
+    let expected = "3";
+    assert_eq!(result, expected);
+
+    let result = process("helper()");
+    let expected = "helper()\n^\nError: Identifier not found";
+    assert_eq!(result, expected);
+
+    let result = process("fn no_globals() { k }\nno_globals()");
+    let expected = "k \n^\nError: Identifier not found";
+    assert_eq!(result, expected);
+
+    let result = process("fn parity(n) {\nfn even(k) { if k == 0 { return 1 }\nreturn odd(k - 1) }\nfn odd(k) { if k == 0 { return 0 }\nreturn even(k - 1) }\nreturn [even(n), odd(n)]\n}\nparity(7)");
+    let expected = "[0, 1]";
+    assert_eq!(result, expected);
+
+    let result = process("fn evens() {\nfn even(k) { if k == 0 { return 1 }\nreturn odd(k - 1) }\nfn odd(k) { if k == 0 { return 0 }\nreturn even(k - 1) }\nreturn even\n}\nis_even = evens(); map(is_even, [3, 4])");
+    let expected = "[0, 1]";
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn default_and_named_arguments() {
+    let _session = lock_session();
+
+    let result = process("fn round_to(x, dp = 2) { round(x * 10^dp) / 10^dp }\nround_to(3.14159)");
+    let expected = "3.14";
+    assert_eq!(result, expected);
+
+    let result = process("round_to(3.14159, 3)");
+    let expected = "3.142";
+    assert_eq!(result, expected);
+
+    let result = process("round_to(dp = 1, x = 3.14159)");
+    let expected = "3.1";
+    assert_eq!(result, expected);
+
+    let result = process("fn avg(xs...) { sum(xs) / len(xs) }\navg(1, 2, 3, 4)");
+    let expected = "2.5";
+    assert_eq!(result, expected);
+
+    let result = process("fn f(a, b = a * 2, rest...) { [a, b, rest] }\nf(1)");
+    let expected = "[1, 2, []]";
+    assert_eq!(result, expected);
+
+    let result = process("f(1, 5, 6, 7)");
+    let expected = "[1, 5, [6, 7]]";
+    assert_eq!(result, expected);
+
+    let result = process("f(b = 0, a = 3)");
+    let expected = "[3, 0, []]";
+    assert_eq!(result, expected);
+
+    let result = process("x = 5; x(2)");
+    let expected = "10";
+    assert_eq!(result, expected);
+
+    let result = process("apply(avg, [1, 2, 3])");
+    let expected = "2";
+    assert_eq!(result, expected);
+
+    let result = process("round_to()");
+    let expected = "round_to()\n^\nError: Invalid number of arguments passed to function, 'x' is missing";
+    assert_eq!(result, expected);
+
+    let result = process("round_to(1, 2, 3)");
+    let expected = "round_to(1, 2, 3)\n^\nError: Invalid number of arguments passed to function, expected 1 to 2";
+    assert_eq!(result, expected);
+
+    let result = process("round_to(1, digits = 2)");
+    let expected = "round_to(1, digits = 2)\n^\nError: Invalid number of arguments passed to function, unexpected 'digits'";
+    assert_eq!(result, expected);
+
+    let result = process("round_to(1, x = 2)");
+    let expected = "round_to(1, x = 2)\n^\nError: Invalid number of arguments passed to function, unexpected 'x'";
+    assert_eq!(result, expected);
+
+    let result = process("f(1, rest = [2])");
+    let expected = "f(1, rest = [2])\n^\nError: Invalid number of arguments passed to function, unexpected 'rest'";
+    assert_eq!(result, expected);
+
+    let result = process("sin(x = 1)");
+    let expected = "sin(x = 1)\n^\nError: Invalid number of arguments passed to function, unexpected 'x'";
+    assert_eq!(result, expected);
+
+    let result = process("fn g(a = 1, b) { a }");
+    let expected = "fn g(a = 1, b) { a }\n            ^\nError: Syntax error";
+    assert_eq!(result, expected);
+
+    let result = process("round_to(dp = 1, 3.14159)");
+    let expected = "round_to(dp = 1, 3.14159)\n                 ^\nError: Syntax error";
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn overloading() {
+    let _session = lock_session();
+
+    let result = process("fn area(r) { pi * r^2 }\nfn area(w, h) { w * h }\narea(1)");
+    let expected = "3.141593";
+    assert_eq!(result, expected);
+
+    let result = process("area(2, 3)");
+    let expected = "6";
+    assert_eq!(result, expected);
+
+    let result = process("area");
+    let expected = "area";
+    assert_eq!(result, expected);
+
+    let result = process("apply(area, [2, 5])");
+    let expected = "10";
+    assert_eq!(result, expected);
+
+    let result = process("map(area, [1])");
+    let expected = "[3.141593]";
+    assert_eq!(result, expected);
+
+    let result = process("fn area(w, h) { 2 * w * h }\narea(2, 3)");
+    let expected = "12";
+    assert_eq!(result, expected);
+
+    let result = process("fn f(n) { if n == 0 { return f(1, 2) }\nreturn n }\nfn f(a, b) { a + b }\nf(0)");
+    let expected = "3";
+    assert_eq!(result, expected);
+
+    let result = process("fn g(x, y = 1) { x + y }\nfn g(x) { 10 * x }\ng(1)");
+    let expected = "10";
+    assert_eq!(result, expected);
+
+    let result = process("g(1, 2)");
+    let expected = "3";
+    assert_eq!(result, expected);
+
+    let result = process("g(x = 1, y = 5)");
+    let expected = "6";
+    assert_eq!(result, expected);
+
+    let result = process("area()");
+    let expected = "area()\n^\nError: Invalid number of arguments passed to function, expected 1 or 2";
+    assert_eq!(result, expected);
+
+    let result = process("fn h(xs...) { xs }\nfn h(a, b, c) { a }\nh(1, 2, 3)");
+    let expected = "1";
+    assert_eq!(result, expected);
+
+    let result = process("h(1, 2)");
+    let expected = "[1, 2]";
+    assert_eq!(result, expected);
+
+    let result = process("fn shapes() {\nfn size(r) { pi * r^2 }\nfn size(w, h) { w * h }\nreturn [size(1), size(2, 3)]\n}\nshapes()");
+    let expected = "[3.141593, 6]";
+    assert_eq!(result, expected);
+
+    let result = process("fn scaled(k) {\nfn s(x) { k * x }\nk = 10\nfn s(x, y) { k * (x + y) }\nreturn s\n}\ns2 = scaled(2); [s2(1), s2(1, 2)]");
+    let expected = "[2, 30]";
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn destructuring() {
+    let _session = lock_session();
+
+    let result = process("fn divmod(a, b) { return a // b, a % b }\nq, r = divmod(17, 5); q * 10 + r");
+    let expected = "32";
+    assert_eq!(result, expected);
+
+    let result = process("divmod(17, 5)");
+    let expected = "[3, 2]";
+    assert_eq!(result, expected);
+
+    let result = process("a = 1; b = 2\na, b = b, a\n[a, b]");
+    let expected = "[2, 1]";
+    assert_eq!(result, expected);
+
+    let result = process("a, b = [3, 4]");
+    let expected = "[3, 4]";
+    assert_eq!(result, expected);
+
+    let result = process("fn f() { x, y = 3, 4\nreturn x * y }\nf()");
+    let expected = "12";
+    assert_eq!(result, expected);
+
+    let result = process("fn g() { return 1 }\ng()");
+    let expected = "1";
+    assert_eq!(result, expected);
+
+    let result = process("a, b = 1, 2, 3");
+    let expected = "a, b = 1, 2, 3\n^\nError: Invalid assignment";
+    assert_eq!(result, expected);
+
+    let result = process("a, b = [1]");
+    let expected = "a, b = [1]\n^\nError: Invalid assignment";
+    assert_eq!(result, expected);
+
+    let result = process("a, b = 5");
+    let expected = "a, b = 5\n^\nError: Wrong type of value";
+    assert_eq!(result, expected);
+
+    let result = process("x, pi = 1, 2");
+    let expected = "x, pi = 1, 2\n   ^\nError: Invalid assignment";
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn tail_calls() {
+    let _session = lock_session();
+
+    let result = process("fn sum_to(n, acc = 0) {\nif n == 0 { return acc }\nreturn sum_to(n - 1, acc + n)\n}\nsum_to(100000)");
+    let expected = "5000050000";
+    assert_eq!(result, expected);
+
+    let result = process("fn even(n) { if n == 0 { return 1 }\nreturn odd(n - 1) }\nfn odd(n) { if n == 0 { return 0 }\nreturn even(n - 1) }\neven(1001)");
+    let expected = "0";
+    assert_eq!(result, expected);
+
+    let result = process("fn count(n) {\nfor i in 0..1 { if n > 0 { return count(n - 1) } }\nreturn n\n}\ncount(1000)");
+    let expected = "0";
+    assert_eq!(result, expected);
+
+    let result = process("fn outer(n) {\nfn go(k, acc) { if k == 0 { return acc }\nreturn go(k - 1, acc + 2) }\nreturn go(n, 0)\n}\nouter(1000)");
+    let expected = "2000";
+    assert_eq!(result, expected);
+
+    let result = process("fn mult(x) { a = 3\nreturn a(x) }\nmult(2)");
+    let expected = "6";
+    assert_eq!(result, expected);
+
+    let result = process("fn root(x) { return sqrt(x) }\nroot(16)");
+    let expected = "4";
+    assert_eq!(result, expected);
+
+    let result = process("fn deep(n) { if n == 0 { return 0 }\nreturn 1 + deep(n - 1) }\ndeep(1000)");
+    let expected = "Error: Maximum iteration count reached";
+    assert_eq!(result, expected);
+
+    let result = process("fn none() {}\nfn tail_none() { return none() }\ntail_none() + 1");
+    let expected = "none() \n^\nError: Function did not return a value";
+    assert_eq!(result, expected);
+
+    let result = process("fn missing(x) { return other(x) }\nmissing(1)");
+    let expected = "other(x) \n^\nError: Identifier not found";
+    assert_eq!(result, expected);
+}
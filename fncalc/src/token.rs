@@ -1,12 +1,17 @@
-use crate::function::FunctionCall;
+use crate::branch::Conditional;
+use crate::format::FormatString;
+use crate::function::{Function, FunctionCall};
+use crate::list::{Comprehension, Index, ListLiteral};
 use crate::Value;
 use std::fmt;
+use std::sync::Arc;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Operator {
     Add,
     Sub,
     Mult,
+    ImplicitMult,
     Div,
     Pow,
     Mod,
@@ -19,84 +24,65 @@ pub enum Operator {
     Equal,
     NotEqual,
     Assign,
-
-    Sin,
-    Sind,
-    Asin,
-    Asind,
-
-    Cos,
-    Cosd,
-    Acos,
-    Acosd,
-
-    Tan,
-    Tand,
-    Atan,
-    Atand,
-
-    Ln,
-    Log,
-    Abs,
+    BitAnd,
+    BitOr,
+    BitXor,
+    BitNot,
+    ShiftLeft,
+    ShiftRight,
+    IntDiv,
+    Range,
 }
 
 impl Operator {
-    pub fn precedence(&self) -> i8 {
+    // `legacy` selects the rules of earlier versions,
+    // where unary minus binds tighter than '^' and '^' is left-associative
+    pub fn precedence(&self, legacy: bool) -> i8 {
         match self {
-            Operator::Add => 3,
-            Operator::Sub => 3,
-            Operator::Mult => 4,
-            Operator::Div => 4,
-            Operator::Mod => 4,
-            Operator::Pow => 5,
-            Operator::Neg => 6,
+            Operator::Add => 8,
+            Operator::Sub => 8,
+            Operator::Mult => 9,
+            Operator::Div => 9,
+            Operator::IntDiv => 9,
+            Operator::Mod => 9,
+            Operator::ImplicitMult => 10,
+            Operator::Pow => 12,
+            Operator::Neg | Operator::BitNot => {
+                if legacy {
+                    13
+                } else {
+                    11
+                }
+            }
             Operator::And => 2,
             Operator::Or => 2,
-            Operator::Not => 6,
+            Operator::Not => 13,
+            // so that '0..n + 1' ends at n + 1
+            Operator::Range => 3,
+            // like in C, but with the shifts between the bitwise and the arithmetic operators
+            Operator::BitOr => 4,
+            Operator::BitXor => 5,
+            Operator::BitAnd => 6,
+            Operator::ShiftLeft => 7,
+            Operator::ShiftRight => 7,
             Operator::LessThan => 1,
             Operator::GreaterThan => 1,
             Operator::Equal => 1,
             Operator::NotEqual => 1,
             Operator::Assign => 0,
-
-            Operator::Sin => 6,
-            Operator::Sind => 6,
-            Operator::Asin => 6,
-            Operator::Asind => 6,
-
-            Operator::Cos => 6,
-            Operator::Cosd => 6,
-            Operator::Acos => 6,
-            Operator::Acosd => 6,
-
-            Operator::Tan => 6,
-            Operator::Tand => 6,
-            Operator::Atan => 6,
-            Operator::Atand => 6,
-
-            Operator::Ln => 6,
-            Operator::Log => 6,
-            Operator::Abs => 6,
         }
     }
 
-    pub fn is_left_associative(&self) -> bool {
+    pub fn is_left_associative(&self, legacy: bool) -> bool {
         match self {
-            Operator::Add => true,
-            Operator::Sub => true,
-            Operator::Mult => true,
-            Operator::Div => true,
-            Operator::Mod => true,
-            Operator::Pow => true,
-            Operator::And => true,
-            Operator::Or => true,
-            Operator::LessThan => true,
-            Operator::GreaterThan => true,
-            Operator::Equal => true,
-            Operator::NotEqual => true,
-            _ => false,
+            Operator::Pow => legacy,
+            _ => !self.is_unary() && *self != Operator::Assign,
         }
     }
+
+    pub fn is_unary(&self) -> bool {
+        matches!(self, Operator::Neg | Operator::Not | Operator::BitNot)
+    }
 }
 
 impl fmt::Display for Operator {
@@ -105,6 +91,7 @@ impl fmt::Display for Operator {
             Operator::Add => write!(f, "+"),
             Operator::Sub => write!(f, "-"),
             Operator::Mult => write!(f, "*"),
+            Operator::ImplicitMult => write!(f, "*"),
             Operator::Div => write!(f, "/"),
             Operator::Mod => write!(f, "%"),
             Operator::Pow => write!(f, "^"),
@@ -117,25 +104,14 @@ impl fmt::Display for Operator {
             Operator::Equal => write!(f, "=="),
             Operator::NotEqual => write!(f, "!="),
             Operator::Assign => write!(f, "="),
-
-            Operator::Sin => write!(f, "sin"),
-            Operator::Sind => write!(f, "sind"),
-            Operator::Asin => write!(f, "asin"),
-            Operator::Asind => write!(f, "asind"),
-
-            Operator::Cos => write!(f, "cos"),
-            Operator::Cosd => write!(f, "cosd"),
-            Operator::Acos => write!(f, "acos"),
-            Operator::Acosd => write!(f, "acosd"),
-
-            Operator::Tan => write!(f, "tan"),
-            Operator::Tand => write!(f, "tand"),
-            Operator::Atan => write!(f, "atan"),
-            Operator::Atand => write!(f, "atand"),
-
-            Operator::Ln => write!(f, "ln"),
-            Operator::Log => write!(f, "log"),
-            Operator::Abs => write!(f, "abs"),
+            Operator::BitAnd => write!(f, "&"),
+            Operator::BitOr => write!(f, "|"),
+            Operator::BitXor => write!(f, "xor"),
+            Operator::BitNot => write!(f, "~"),
+            Operator::ShiftLeft => write!(f, "<<"),
+            Operator::ShiftRight => write!(f, ">>"),
+            Operator::IntDiv => write!(f, "//"),
+            Operator::Range => write!(f, ".."),
         }
     }
 }
@@ -158,8 +134,16 @@ impl fmt::Display for Parenthesis {
 #[derive(Debug, Clone)]
 pub enum TokenKind {
     Value(Value),
+    // a hex, binary or octal number literal
+    Bits(Value),
     Identifier(String),
     FunctionCall(FunctionCall),
+    Conditional(Conditional),
+    Lambda(Arc<Function>),
+    List(ListLiteral),
+    Comprehension(Comprehension),
+    FormatString(FormatString),
+    Index(Index),
     Operator(Operator),
     Parenthesis(Parenthesis),
 }
@@ -167,9 +151,15 @@ pub enum TokenKind {
 impl fmt::Display for TokenKind {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            TokenKind::Value(x) => write!(f, "{x}"),
+            TokenKind::Value(x) | TokenKind::Bits(x) => write!(f, "{x}"),
             TokenKind::Identifier(x) => write!(f, "{x}"),
             TokenKind::FunctionCall(x) => write!(f, "{x}"),
+            TokenKind::Conditional(x) => write!(f, "{x}"),
+            TokenKind::Lambda(x) => write!(f, "{x}"),
+            TokenKind::List(x) => write!(f, "{x}"),
+            TokenKind::Comprehension(x) => write!(f, "{x}"),
+            TokenKind::FormatString(x) => write!(f, "{x}"),
+            TokenKind::Index(x) => write!(f, "{x}"),
             TokenKind::Operator(x) => write!(f, "{x}"),
             TokenKind::Parenthesis(x) => write!(f, "{x}"),
         }
@@ -190,6 +180,13 @@ impl Token {
         }
     }
 
+    pub fn new_bits(pos: usize, value: Value) -> Token {
+        Token {
+            pos,
+            kind: TokenKind::Bits(value),
+        }
+    }
+
     pub fn new_identifier(pos: usize, value: String) -> Token {
         Token {
             pos,
@@ -204,6 +201,48 @@ impl Token {
         }
     }
 
+    pub fn new_conditional(pos: usize, value: Conditional) -> Token {
+        Token {
+            pos,
+            kind: TokenKind::Conditional(value),
+        }
+    }
+
+    pub fn new_lambda(pos: usize, value: Arc<Function>) -> Token {
+        Token {
+            pos,
+            kind: TokenKind::Lambda(value),
+        }
+    }
+
+    pub fn new_list(pos: usize, value: ListLiteral) -> Token {
+        Token {
+            pos,
+            kind: TokenKind::List(value),
+        }
+    }
+
+    pub fn new_comprehension(pos: usize, value: Comprehension) -> Token {
+        Token {
+            pos,
+            kind: TokenKind::Comprehension(value),
+        }
+    }
+
+    pub fn new_format_string(pos: usize, value: FormatString) -> Token {
+        Token {
+            pos,
+            kind: TokenKind::FormatString(value),
+        }
+    }
+
+    pub fn new_index(pos: usize, value: Index) -> Token {
+        Token {
+            pos,
+            kind: TokenKind::Index(value),
+        }
+    }
+
     pub fn new_operator(pos: usize, value: Operator) -> Token {
         Token {
             pos,
@@ -0,0 +1,1235 @@
+use crate::function::Callable;
+use crate::instruction::{self, ErrorKind};
+use crate::integer;
+use crate::number::{self, Number};
+use crate::precise;
+use crate::session;
+use crate::LOOP_LIMIT;
+use bigdecimal::BigDecimal;
+use rust_decimal::prelude::*;
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::Neg;
+
+// complex values only come up if complex numbers are enabled in the session settings,
+// a complex result without an imaginary part is always turned back into a real one.
+// Rational values come from number literals in the exact number mode, and turn into
+// real ones as soon as they are combined with one.
+// Precise values come from the precise number mode, combined with a real or rational
+// value they stay precise, combined with a complex one they turn into a real one.
+// Float values come from the float number mode, and turn everything but complex values
+// they are combined with into floats as well.
+// Integer values come from the integer mode, and behave like rational ones when they are
+// combined with another kind of number.
+// Lists and strings can't be used in calculations, strings can only be joined with '+'
+// and compared, and they are the only values which are true if they are not empty.
+// Functions can only be called, passed around and compared.
+#[derive(Debug, Clone)]
+pub enum Value {
+    Real(Decimal),
+    Complex(Complex),
+    Rational(Rational),
+    Precise(BigDecimal),
+    Float(f64),
+    Integer(i128),
+    List(Vec<Value>),
+    String(String),
+    Function(Callable),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Complex {
+    re: Decimal,
+    im: Decimal,
+}
+
+// always reduced, with a positive denominator, and both parts fit into the mantissa
+// of a Decimal so that it can be converted into one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rational {
+    num: i128,
+    den: i128,
+}
+
+impl Value {
+    pub const I: Value = Value::Complex(Complex {
+        re: Decimal::ZERO,
+        im: Decimal::ONE,
+    });
+
+    pub fn complex(re: Decimal, im: Decimal) -> Value {
+        if im.is_zero() {
+            Value::Real(re)
+        } else {
+            Value::Complex(Complex { re, im })
+        }
+    }
+
+    // a Decimal if the number fits into one exactly, otherwise it stays precise
+    pub fn from_precise(x: BigDecimal) -> Value {
+        match precise::to_decimal(&x) {
+            Some(decimal) if precise::from_decimal(decimal) == x => Value::Real(decimal),
+            _ => Value::Precise(x),
+        }
+    }
+
+    // precise and float values have to be converted with to_decimal first,
+    // lists are rejected before
+    pub fn re(&self) -> Decimal {
+        match self {
+            Value::Real(x) => *x,
+            Value::Complex(z) => z.re,
+            Value::Rational(q) => q.to_decimal(),
+            Value::Integer(x) => {
+                Decimal::from_i128(*x).expect("Integers always fit into a Decimal")
+            }
+            Value::Precise(_) | Value::Float(_) => {
+                unreachable!("The parts of precise and float values are not Decimals")
+            }
+            Value::List(_) | Value::String(_) | Value::Function(_) => {
+                unreachable!("Lists, strings and functions have no parts")
+            }
+        }
+    }
+
+    pub fn im(&self) -> Decimal {
+        match self {
+            Value::Complex(z) => z.im,
+            _ => Decimal::ZERO,
+        }
+    }
+
+    pub fn conj(&self) -> Value {
+        match self {
+            Value::Complex(z) => Value::complex(z.re, -z.im),
+            _ => self.clone(),
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        match self {
+            Value::Rational(q) => q.num == 0,
+            Value::Precise(x) => x.is_zero(),
+            Value::Float(x) => *x == 0.0,
+            Value::Integer(x) => *x == 0,
+            Value::List(elements) => elements.is_empty(),
+            Value::String(string) => string.is_empty(),
+            Value::Function(_) => false,
+            _ => self.re().is_zero() && self.im().is_zero(),
+        }
+    }
+
+    // the value as a Decimal based one, for functions without exact results
+    pub fn to_real(&self) -> Value {
+        match self {
+            Value::Rational(_) | Value::Integer(_) => Value::Real(self.re()),
+            _ => self.clone(),
+        }
+    }
+
+    // the value as a Decimal based one, for functions without a precise or float
+    // implementation, infinite and NaN floats are out of range
+    pub fn to_decimal(&self, context: &str, pos: usize) -> Result<Value, instruction::Error> {
+        match self {
+            Value::Precise(x) => match precise::to_decimal(x) {
+                Some(x) => Ok(Value::Real(x)),
+                None => Err(error(context, pos, ErrorKind::OutOfRange)),
+            },
+            Value::Float(x) => match Decimal::from_f64(*x) {
+                Some(x) => Ok(Value::Real(x)),
+                None => Err(error(context, pos, ErrorKind::OutOfRange)),
+            },
+            _ => Ok(self.clone()),
+        }
+    }
+
+    // None for complex and float values, lists, strings and functions
+    fn to_precise(&self, precision: u64) -> Option<BigDecimal> {
+        match self {
+            Value::Real(x) => Some(precise::from_decimal(*x)),
+            Value::Rational(q) => precise::div(&q.num.into(), &q.den.into(), precision),
+            Value::Precise(x) => Some(x.clone()),
+            Value::Integer(x) => Some(BigDecimal::from(*x)),
+            Value::Complex(_)
+            | Value::Float(_)
+            | Value::List(_)
+            | Value::String(_)
+            | Value::Function(_) => None,
+        }
+    }
+
+    // like real, for operations on floats
+    pub fn float(&self, context: &str, pos: usize) -> Result<f64, instruction::Error> {
+        match self {
+            Value::Float(x) => Ok(*x),
+            Value::Real(x) => Ok(x.to_f64().expect("Every Decimal is a valid f64")),
+            Value::Rational(q) => Ok(q.num as f64 / q.den as f64),
+            Value::Integer(x) => Ok(*x as f64),
+            Value::Precise(x) => Ok(x.to_f64().unwrap_or(f64::NAN)),
+            Value::Complex(_) => Err(error(context, pos, ErrorKind::NotReal)),
+            Value::List(_) | Value::String(_) | Value::Function(_) => {
+                Err(error(context, pos, ErrorKind::TypeError))
+            }
+        }
+    }
+
+    // for operations which are only defined on real numbers
+    pub fn real(&self, context: &str, pos: usize) -> Result<Decimal, instruction::Error> {
+        match self {
+            Value::Real(x) => Ok(*x),
+            Value::Rational(_) | Value::Integer(_) => Ok(self.re()),
+            Value::Precise(_) | Value::Float(_) => {
+                self.to_decimal(context, pos)?.real(context, pos)
+            }
+            Value::Complex(_) => Err(error(context, pos, ErrorKind::NotReal)),
+            Value::List(_) | Value::String(_) | Value::Function(_) => {
+                Err(error(context, pos, ErrorKind::TypeError))
+            }
+        }
+    }
+
+    // both operands of a binary operation as the same kind of number, if one of them is
+    // a float or precise the other one is made one as well, unless it is complex.
+    // Lists and strings are rejected here, so they never reach the calculations.
+    fn promote(
+        &self,
+        rhs: &Value,
+        context: &str,
+        pos: usize,
+    ) -> Result<(Value, Value), instruction::Error> {
+        match (self, rhs) {
+            (Value::List(_) | Value::String(_) | Value::Function(_), _)
+            | (_, Value::List(_) | Value::String(_) | Value::Function(_)) => {
+                Err(error(context, pos, ErrorKind::TypeError))
+            }
+            (Value::Precise(_) | Value::Float(_), Value::Complex(_))
+            | (Value::Complex(_), Value::Precise(_) | Value::Float(_)) => Ok((
+                self.to_decimal(context, pos)?,
+                rhs.to_decimal(context, pos)?,
+            )),
+            (Value::Float(_), _) | (_, Value::Float(_)) => Ok((
+                Value::Float(self.float(context, pos)?),
+                Value::Float(rhs.float(context, pos)?),
+            )),
+            (Value::Precise(_), _) | (_, Value::Precise(_)) => {
+                let precision = precise::precision();
+                let lhs = self
+                    .to_precise(precision)
+                    .expect("Only complex values can't be precise");
+                let rhs = rhs
+                    .to_precise(precision)
+                    .expect("Only complex values can't be precise");
+                Ok((Value::Precise(lhs), Value::Precise(rhs)))
+            }
+            _ => Ok((self.clone(), rhs.clone())),
+        }
+    }
+
+    pub fn add(&self, rhs: &Value, context: &str, pos: usize) -> Result<Value, instruction::Error> {
+        if let (Value::String(lhs), Value::String(rhs)) = (self, rhs) {
+            return Ok(Value::String(format!("{lhs}{rhs}")));
+        }
+
+        match self.promote(rhs, context, pos)? {
+            (Value::Real(lhs), Value::Real(rhs)) => {
+                number::binary(lhs, rhs, Number::math_add, context, pos)
+            }
+            (Value::Float(lhs), Value::Float(rhs)) => {
+                number::binary(lhs, rhs, Number::math_add, context, pos)
+            }
+            (Value::Precise(lhs), Value::Precise(rhs)) => Ok(Value::Precise(precise::add(
+                &lhs,
+                &rhs,
+                precise::precision(),
+            ))),
+            (Value::Integer(a), Value::Integer(b)) => Ok(Value::Integer(integer::result(
+                a.checked_add(b),
+                a.wrapping_add(b),
+                context,
+                pos,
+            )?)),
+            (lhs, rhs) => {
+                if let (Value::Rational(a), Value::Rational(b)) = (&lhs, &rhs) {
+                    if let Some(sum) = a.checked_add(b) {
+                        return Ok(Value::Rational(sum));
+                    }
+                }
+                Ok(Value::complex(
+                    out_of_range(lhs.re().checked_add(rhs.re()), context, pos)?,
+                    out_of_range(lhs.im().checked_add(rhs.im()), context, pos)?,
+                ))
+            }
+        }
+    }
+
+    pub fn sub(&self, rhs: &Value, context: &str, pos: usize) -> Result<Value, instruction::Error> {
+        let (lhs, rhs) = self.promote(rhs, context, pos)?;
+        lhs.add(&-rhs, context, pos)
+    }
+
+    pub fn mul(&self, rhs: &Value, context: &str, pos: usize) -> Result<Value, instruction::Error> {
+        match self.promote(rhs, context, pos)? {
+            (Value::Real(lhs), Value::Real(rhs)) => {
+                number::binary(lhs, rhs, Number::math_mul, context, pos)
+            }
+            (Value::Float(lhs), Value::Float(rhs)) => {
+                number::binary(lhs, rhs, Number::math_mul, context, pos)
+            }
+            (Value::Precise(lhs), Value::Precise(rhs)) => Ok(Value::Precise(precise::mul(
+                &lhs,
+                &rhs,
+                precise::precision(),
+            ))),
+            (Value::Integer(a), Value::Integer(b)) => Ok(Value::Integer(integer::result(
+                a.checked_mul(b),
+                a.wrapping_mul(b),
+                context,
+                pos,
+            )?)),
+            (lhs, rhs) => {
+                if let (Value::Rational(a), Value::Rational(b)) = (&lhs, &rhs) {
+                    if let Some(product) = a.checked_mul(b) {
+                        return Ok(Value::Rational(product));
+                    }
+                }
+                out_of_range(lhs.checked_mul(&rhs), context, pos)
+            }
+        }
+    }
+
+    // dividing a float by zero gives inf or NaN instead of an error,
+    // dividing integers truncates the quotient like in C
+    pub fn div(&self, rhs: &Value, context: &str, pos: usize) -> Result<Value, instruction::Error> {
+        match self.promote(rhs, context, pos)? {
+            (Value::Real(lhs), Value::Real(rhs)) => {
+                number::binary(lhs, rhs, Number::math_div, context, pos)
+            }
+            (Value::Float(lhs), Value::Float(rhs)) => {
+                number::binary(lhs, rhs, Number::math_div, context, pos)
+            }
+            _ if rhs.is_zero() => Err(error(context, pos, ErrorKind::ZeroDivision)),
+            (Value::Precise(lhs), Value::Precise(rhs)) => Ok(Value::Precise(
+                precise::div(&lhs, &rhs, precise::precision()).expect("The divisor is not zero"),
+            )),
+            (Value::Integer(a), Value::Integer(b)) => Ok(Value::Integer(integer::result(
+                a.checked_div(b),
+                a.wrapping_div(b),
+                context,
+                pos,
+            )?)),
+            (lhs, rhs) => {
+                if let (Value::Rational(a), Value::Rational(b)) = (&lhs, &rhs) {
+                    if let Some(quotient) = a.checked_div(b) {
+                        return Ok(Value::Rational(quotient));
+                    }
+                }
+                out_of_range(lhs.checked_div(&rhs), context, pos)
+            }
+        }
+    }
+
+    pub fn compare(
+        &self,
+        rhs: &Value,
+        context: &str,
+        pos: usize,
+    ) -> Result<Ordering, instruction::Error> {
+        if let (Value::String(lhs), Value::String(rhs)) = (self, rhs) {
+            return Ok(lhs.cmp(rhs));
+        }
+
+        match self.promote(rhs, context, pos)? {
+            (Value::Float(lhs), Value::Float(rhs)) => Ok(lhs.compare(&rhs)),
+            (Value::Precise(lhs), Value::Precise(rhs)) => Ok(lhs.cmp(&rhs)),
+            (lhs, rhs) => {
+                if let (Value::Rational(a), Value::Rational(b)) = (&lhs, &rhs) {
+                    if let Some(ordering) = a.compare(b) {
+                        return Ok(ordering);
+                    }
+                }
+                Ok(lhs.real(context, pos)?.cmp(&rhs.real(context, pos)?))
+            }
+        }
+    }
+
+    // the remainder has the sign of the dividend
+    pub fn rem(&self, rhs: &Value, context: &str, pos: usize) -> Result<Value, instruction::Error> {
+        match self.promote(rhs, context, pos)? {
+            (Value::Real(lhs), Value::Real(rhs)) => {
+                number::binary(lhs, rhs, Number::math_rem, context, pos)
+            }
+            (Value::Float(lhs), Value::Float(rhs)) => {
+                number::binary(lhs, rhs, Number::math_rem, context, pos)
+            }
+            _ if rhs.is_zero() => Err(error(context, pos, ErrorKind::ZeroDivision)),
+            (Value::Precise(lhs), Value::Precise(rhs)) => {
+                Ok(Value::Precise(precise::rem(&lhs, &rhs)))
+            }
+            (Value::Integer(a), Value::Integer(b)) => Ok(Value::Integer(integer::result(
+                a.checked_rem(b),
+                a.wrapping_rem(b),
+                context,
+                pos,
+            )?)),
+            (lhs, rhs) => {
+                if let (Value::Rational(a), Value::Rational(b)) = (&lhs, &rhs) {
+                    if let Some(remainder) = a.checked_rem(b) {
+                        return Ok(Value::Rational(remainder));
+                    }
+                }
+                number::binary(
+                    lhs.real(context, pos)?,
+                    rhs.real(context, pos)?,
+                    Number::math_rem,
+                    context,
+                    pos,
+                )
+            }
+        }
+    }
+
+    pub fn abs(&self, context: &str, pos: usize) -> Result<Decimal, instruction::Error> {
+        match self {
+            Value::Complex(z) => z.re.math_hypot(z.im, context, pos),
+            _ => Ok(self.real(context, pos)?.abs()),
+        }
+    }
+
+    // angle in radians between the positive real axis and the value
+    pub fn arg(&self, context: &str, pos: usize) -> Result<Decimal, instruction::Error> {
+        match self {
+            Value::Precise(_) | Value::Float(_) => self.to_decimal(context, pos)?.arg(context, pos),
+            _ => self.im().trig_atan2(self.re(), context, pos),
+        }
+    }
+
+    pub fn sqrt(&self, context: &str, pos: usize) -> Result<Value, instruction::Error> {
+        match self {
+            Value::Precise(x) => match precise::sqrt(x, precise::precision()) {
+                Some(root) => Ok(Value::Precise(root)),
+                None => self.to_decimal(context, pos)?.sqrt(context, pos),
+            },
+            Value::Float(x) if *x < 0.0 && complex_enabled() => {
+                self.to_decimal(context, pos)?.sqrt(context, pos)
+            }
+            Value::Float(x) => number::unary(*x, Number::math_sqrt, context, pos),
+            Value::Rational(_) | Value::Integer(_) => self.to_real().sqrt(context, pos),
+            Value::List(_) | Value::String(_) | Value::Function(_) => {
+                Err(error(context, pos, ErrorKind::TypeError))
+            }
+            Value::Real(x) if x.is_sign_negative() && complex_enabled() => {
+                Ok(Value::complex(Decimal::ZERO, (-x).math_sqrt(context, pos)?))
+            }
+            Value::Real(x) => number::unary(*x, Number::math_sqrt, context, pos),
+            Value::Complex(z) => {
+                let r = self.abs(context, pos)?;
+                let re = ((r + z.re) / Decimal::TWO).max(Decimal::ZERO).math_sqrt(context, pos)?;
+                let im = ((r - z.re) / Decimal::TWO).max(Decimal::ZERO).math_sqrt(context, pos)?;
+                Ok(Value::complex(re, if z.im.is_sign_negative() { -im } else { im }))
+            }
+        }
+    }
+
+    pub fn exp(&self, context: &str, pos: usize) -> Result<Value, instruction::Error> {
+        match self {
+            Value::Precise(x) => match precise::exp(x, precise::precision()) {
+                Some(power) => Ok(Value::Precise(power)),
+                None => Err(error(context, pos, ErrorKind::MathError)),
+            },
+            Value::Float(x) => number::unary(*x, Number::math_exp, context, pos),
+            Value::Rational(_) | Value::Integer(_) => self.to_real().exp(context, pos),
+            Value::List(_) | Value::String(_) | Value::Function(_) => {
+                Err(error(context, pos, ErrorKind::TypeError))
+            }
+            Value::Real(x) => number::unary(*x, Number::math_exp, context, pos),
+            Value::Complex(z) => {
+                let magnitude = z.re.math_exp(context, pos)?;
+                Ok(Value::complex(
+                    magnitude * z.im.trig_cos(context, pos)?,
+                    magnitude * z.im.trig_sin(context, pos)?,
+                ))
+            }
+        }
+    }
+
+    pub fn ln(&self, context: &str, pos: usize) -> Result<Value, instruction::Error> {
+        match self {
+            Value::Precise(x) => match precise::ln(x, precise::precision()) {
+                Some(logarithm) => Ok(Value::Precise(logarithm)),
+                None => self.to_decimal(context, pos)?.ln(context, pos),
+            },
+            Value::Float(x) if *x < 0.0 && complex_enabled() => {
+                self.to_decimal(context, pos)?.ln(context, pos)
+            }
+            Value::Float(x) => number::unary(*x, Number::math_ln, context, pos),
+            Value::Rational(_) | Value::Integer(_) => self.to_real().ln(context, pos),
+            Value::List(_) | Value::String(_) | Value::Function(_) => {
+                Err(error(context, pos, ErrorKind::TypeError))
+            }
+            Value::Real(x) if x.is_sign_negative() && complex_enabled() => {
+                Ok(Value::complex((-x).math_ln(context, pos)?, Decimal::PI))
+            }
+            Value::Real(x) => number::unary(*x, Number::math_ln, context, pos),
+            Value::Complex(_) => Ok(Value::complex(
+                self.abs(context, pos)?.math_ln(context, pos)?,
+                self.arg(context, pos)?,
+            )),
+        }
+    }
+
+    pub fn log(&self, context: &str, pos: usize) -> Result<Value, instruction::Error> {
+        match self.to_real() {
+            Value::Float(x) if x >= 0.0 || !complex_enabled() => {
+                number::unary(x, Number::math_log, context, pos)
+            }
+            Value::Real(x) if !x.is_sign_negative() || !complex_enabled() => {
+                number::unary(x, Number::math_log, context, pos)
+            }
+            _ => self.log_base(&Value::Real(Decimal::TEN), context, pos),
+        }
+    }
+
+    pub fn log_base(
+        &self,
+        base: &Value,
+        context: &str,
+        pos: usize,
+    ) -> Result<Value, instruction::Error> {
+        if let (Value::Float(x), Value::Float(b)) = self.promote(base, context, pos)? {
+            if (x >= 0.0 && b >= 0.0) || !complex_enabled() {
+                return number::binary(x, b, Number::math_log_base, context, pos);
+            }
+        }
+
+        if let (Value::Precise(x), Value::Precise(b)) = self.promote(base, context, pos)? {
+            if b.is_one() {
+                return Err(error(context, pos, ErrorKind::DomainError));
+            }
+            return match precise::log(&x, &b, precise::precision()) {
+                Some(logarithm) => Ok(Value::Precise(logarithm)),
+                None => self.to_decimal(context, pos)?.log_base(
+                    &base.to_decimal(context, pos)?,
+                    context,
+                    pos,
+                ),
+            };
+        }
+
+        match (self.to_real(), base.to_real()) {
+            (Value::Real(x), Value::Real(b))
+                if !(x.is_sign_negative() || b.is_sign_negative()) || !complex_enabled() =>
+            {
+                number::binary(x, b, Number::math_log_base, context, pos)
+            }
+            _ => {
+                let ln_base = base.ln(context, pos)?;
+                if ln_base.is_zero() {
+                    return Err(error(context, pos, ErrorKind::DomainError));
+                }
+                self.ln(context, pos)?.div(&ln_base, context, pos)
+            }
+        }
+    }
+
+    pub fn pow(&self, rhs: &Value, context: &str, pos: usize) -> Result<Value, instruction::Error> {
+        let (lhs, rhs) = self.promote(rhs, context, pos)?;
+        match (&lhs, &rhs) {
+            (Value::Precise(x), Value::Precise(y)) => {
+                return match precise::pow(x, y, precise::precision()) {
+                    Some(power) => Ok(Value::Precise(power)),
+                    // negative bases with fractional exponents, or zero with a negative one
+                    None => lhs.to_decimal(context, pos)?.pow(
+                        &rhs.to_decimal(context, pos)?,
+                        context,
+                        pos,
+                    ),
+                };
+            }
+            (Value::Float(x), Value::Float(y))
+                if *x >= 0.0 || y.fract() == 0.0 || !complex_enabled() =>
+            {
+                return number::binary(*x, *y, Number::math_pow, context, pos);
+            }
+            (Value::Float(_), Value::Float(_)) => {
+                return lhs.to_decimal(context, pos)?.pow(
+                    &rhs.to_decimal(context, pos)?,
+                    context,
+                    pos,
+                );
+            }
+            _ => (),
+        }
+
+        if let (Value::Integer(base), Value::Integer(exponent)) = (&lhs, &rhs) {
+            if *exponent < 0 {
+                return Err(error(context, pos, ErrorKind::InvalidExponent));
+            }
+            let (checked, wrapping) = integer::pow(*base, exponent.unsigned_abs());
+            return Ok(Value::Integer(integer::result(
+                checked, wrapping, context, pos,
+            )?));
+        }
+
+        if let (Value::Rational(base), Value::Rational(exponent)) = (&lhs, &rhs) {
+            if base.num == 0 && exponent.num < 0 {
+                return Err(error(context, pos, ErrorKind::ZeroDivision));
+            }
+            // integer powers which don't fit are too large to be exact,
+            // instead of being rounded to zero or an inexact number
+            if exponent.den == 1 {
+                return match exponent.to_i64().and_then(|exponent| base.checked_powi(exponent)) {
+                    Some(power) => Ok(Value::Rational(power)),
+                    None => Err(error(context, pos, ErrorKind::OutOfRange)),
+                };
+            }
+        }
+
+        match (lhs.to_real(), rhs.to_real()) {
+            (Value::Real(lhs), Value::Real(rhs))
+                if !lhs.is_sign_negative() || rhs.fract().is_zero() || !complex_enabled() =>
+            {
+                number::binary(lhs, rhs, Number::math_pow, context, pos)
+            }
+            // integer powers are calculated exactly, so that 'i^2' is -1
+            (_, Value::Real(exponent))
+                if exponent.fract().is_zero() && exponent.to_i64().is_some() =>
+            {
+                lhs.powi(exponent.to_i64().unwrap(), context, pos)
+            }
+            _ if lhs.is_zero() => {
+                if rhs.re() > Decimal::ZERO {
+                    Ok(Value::Real(Decimal::ZERO))
+                } else {
+                    Err(error(context, pos, ErrorKind::InvalidExponent))
+                }
+            }
+            _ => rhs
+                .mul(&lhs.ln(context, pos)?, context, pos)?
+                .exp(context, pos),
+        }
+    }
+
+    fn powi(&self, exponent: i64, context: &str, pos: usize) -> Result<Value, instruction::Error> {
+        let math_error = || error(context, pos, ErrorKind::MathError);
+
+        let mut result = Value::Real(Decimal::ONE);
+        let mut base = self.clone();
+        let mut exponent_left = exponent.unsigned_abs();
+        while exponent_left > 0 {
+            if exponent_left % 2 == 1 {
+                result = result.checked_mul(&base).ok_or_else(math_error)?;
+            }
+            exponent_left /= 2;
+            if exponent_left > 0 {
+                base = base.checked_mul(&base).ok_or_else(math_error)?;
+            }
+        }
+
+        if exponent < 0 {
+            Value::Real(Decimal::ONE)
+                .checked_div(&result)
+                .ok_or_else(math_error)
+        } else {
+            Ok(result)
+        }
+    }
+
+    // products and quotients of Decimal based values, None on an overflow or a zero divisor
+    fn checked_mul(&self, rhs: &Value) -> Option<Value> {
+        let (a, b, c, d) = (self.re(), self.im(), rhs.re(), rhs.im());
+        let re = a.checked_mul(c)?.checked_sub(b.checked_mul(d)?)?;
+        let im = a.checked_mul(d)?.checked_add(b.checked_mul(c)?)?;
+        Some(Value::complex(re, im))
+    }
+
+    fn checked_div(&self, rhs: &Value) -> Option<Value> {
+        let (a, b, c, d) = (self.re(), self.im(), rhs.re(), rhs.im());
+        if d.is_zero() {
+            return Some(Value::complex(a.checked_div(c)?, b.checked_div(c)?));
+        }
+
+        let denominator = c.checked_mul(c)?.checked_add(d.checked_mul(d)?)?;
+        let re = a.checked_mul(c)?.checked_add(b.checked_mul(d)?)?;
+        let im = b.checked_mul(c)?.checked_sub(a.checked_mul(d)?)?;
+        Some(Value::complex(
+            re.checked_div(denominator)?,
+            im.checked_div(denominator)?,
+        ))
+    }
+
+    pub fn sin(&self, context: &str, pos: usize) -> Result<Value, instruction::Error> {
+        match self {
+            Value::Precise(x) => {
+                precise_result(precise::sin(x, precise::precision()), context, pos)
+            }
+            Value::Float(x) => number::unary(*x, Number::trig_sin, context, pos),
+            Value::Rational(_) | Value::Integer(_) => self.to_real().sin(context, pos),
+            Value::List(_) | Value::String(_) | Value::Function(_) => {
+                Err(error(context, pos, ErrorKind::TypeError))
+            }
+            Value::Real(x) => number::unary(*x, Number::trig_sin, context, pos),
+            // sin(a + bi) = sin(a)cosh(b) + i cos(a)sinh(b)
+            Value::Complex(z) => Ok(Value::complex(
+                z.re.trig_sin(context, pos)? * z.im.hyp_cosh(context, pos)?,
+                z.re.trig_cos(context, pos)? * z.im.hyp_sinh(context, pos)?,
+            )),
+        }
+    }
+
+    pub fn cos(&self, context: &str, pos: usize) -> Result<Value, instruction::Error> {
+        match self {
+            Value::Precise(x) => {
+                precise_result(precise::cos(x, precise::precision()), context, pos)
+            }
+            Value::Float(x) => number::unary(*x, Number::trig_cos, context, pos),
+            Value::Rational(_) | Value::Integer(_) => self.to_real().cos(context, pos),
+            Value::List(_) | Value::String(_) | Value::Function(_) => {
+                Err(error(context, pos, ErrorKind::TypeError))
+            }
+            Value::Real(x) => number::unary(*x, Number::trig_cos, context, pos),
+            // cos(a + bi) = cos(a)cosh(b) - i sin(a)sinh(b)
+            Value::Complex(z) => Ok(Value::complex(
+                z.re.trig_cos(context, pos)? * z.im.hyp_cosh(context, pos)?,
+                -(z.re.trig_sin(context, pos)? * z.im.hyp_sinh(context, pos)?),
+            )),
+        }
+    }
+
+    pub fn tan(&self, context: &str, pos: usize) -> Result<Value, instruction::Error> {
+        match self {
+            Value::Precise(x) => match precise::tan(x, precise::precision()) {
+                Some(tan) => Ok(Value::Precise(tan)),
+                None => Err(error(context, pos, ErrorKind::DomainError)),
+            },
+            Value::Float(x) => number::unary(*x, Number::trig_tan, context, pos),
+            Value::Rational(_) | Value::Integer(_) => self.to_real().tan(context, pos),
+            Value::List(_) | Value::String(_) | Value::Function(_) => {
+                Err(error(context, pos, ErrorKind::TypeError))
+            }
+            Value::Real(x) => number::unary(*x, Number::trig_tan, context, pos),
+            Value::Complex(_) => {
+                let cos = self.cos(context, pos)?;
+                if cos.is_zero() {
+                    return Err(error(context, pos, ErrorKind::DomainError));
+                }
+                self.sin(context, pos)?.div(&cos, context, pos)
+            }
+        }
+    }
+
+    pub fn asin(&self, context: &str, pos: usize) -> Result<Value, instruction::Error> {
+        match self {
+            Value::Float(x) => number::unary(*x, Number::trig_asin, context, pos),
+            Value::Precise(x) => match precise::asin(x, precise::precision()) {
+                Some(angle) => Ok(Value::Precise(angle)),
+                None => Err(error(context, pos, ErrorKind::DomainError)),
+            },
+            _ => number::unary(self.real(context, pos)?, Number::trig_asin, context, pos),
+        }
+    }
+
+    pub fn acos(&self, context: &str, pos: usize) -> Result<Value, instruction::Error> {
+        match self {
+            Value::Float(x) => number::unary(*x, Number::trig_acos, context, pos),
+            Value::Precise(x) => match precise::acos(x, precise::precision()) {
+                Some(angle) => Ok(Value::Precise(angle)),
+                None => Err(error(context, pos, ErrorKind::DomainError)),
+            },
+            _ => number::unary(self.real(context, pos)?, Number::trig_acos, context, pos),
+        }
+    }
+
+    pub fn atan(&self, context: &str, pos: usize) -> Result<Value, instruction::Error> {
+        match self {
+            Value::Float(x) => number::unary(*x, Number::trig_atan, context, pos),
+            Value::Precise(x) => Ok(Value::Precise(precise::atan(x, precise::precision()))),
+            _ => number::unary(self.real(context, pos)?, Number::trig_atan, context, pos),
+        }
+    }
+
+    // the angle of the point (x, y), called on y
+    pub fn atan2(&self, x: &Value, context: &str, pos: usize) -> Result<Value, instruction::Error> {
+        match self.promote(x, context, pos)? {
+            (Value::Float(y), Value::Float(x)) => {
+                number::binary(y, x, Number::trig_atan2, context, pos)
+            }
+            (Value::Precise(y), Value::Precise(x)) => {
+                Ok(Value::Precise(precise::atan2(&y, &x, precise::precision())))
+            }
+            (y, x) => number::binary(
+                y.real(context, pos)?,
+                x.real(context, pos)?,
+                Number::trig_atan2,
+                context,
+                pos,
+            ),
+        }
+    }
+
+    // unary minus, which can overflow in the integer mode
+    pub fn negate(&self, context: &str, pos: usize) -> Result<Value, instruction::Error> {
+        match self {
+            Value::Integer(x) => Ok(Value::Integer(integer::result(
+                x.checked_neg(),
+                x.wrapping_neg(),
+                context,
+                pos,
+            )?)),
+            Value::List(_) | Value::String(_) | Value::Function(_) => {
+                Err(error(context, pos, ErrorKind::TypeError))
+            }
+            _ => Ok(-self.clone()),
+        }
+    }
+
+    // the quotient rounded towards negative infinity
+    pub fn floor_div(
+        &self,
+        rhs: &Value,
+        context: &str,
+        pos: usize,
+    ) -> Result<Value, instruction::Error> {
+        match self.promote(rhs, context, pos)? {
+            (Value::Integer(a), Value::Integer(b)) if b != 0 => {
+                let quotient = a.checked_div(b).map(|quotient| {
+                    if a % b != 0 && (a < 0) != (b < 0) {
+                        quotient - 1
+                    } else {
+                        quotient
+                    }
+                });
+                Ok(Value::Integer(integer::result(
+                    quotient,
+                    a.wrapping_div(b),
+                    context,
+                    pos,
+                )?))
+            }
+            (lhs, rhs) => match lhs.div(&rhs, context, pos)? {
+                Value::Rational(q) => Ok(Value::Rational(Rational {
+                    num: q.num.div_euclid(q.den),
+                    den: 1,
+                })),
+                Value::Precise(x) => Ok(Value::Precise(precise::floor(&x))),
+                Value::Float(x) => Ok(Value::Float(x.floor())),
+                quotient => Ok(Value::Real(quotient.real(context, pos)?.floor())),
+            },
+        }
+    }
+
+    // the operand of a bitwise operation, which has to be an integer
+    pub fn to_integer(&self, context: &str, pos: usize) -> Result<i128, instruction::Error> {
+        let integer = match self {
+            Value::Integer(x) => Some(*x),
+            Value::Rational(q) if q.den == 1 => Some(q.num),
+            Value::Real(x) if x.fract().is_zero() => x.to_i128(),
+            Value::Precise(x) if x.is_integer() => x.to_i128(),
+            Value::Float(x) if x.fract() == 0.0 => x.to_i128(),
+            Value::List(_) | Value::String(_) | Value::Function(_) => {
+                return Err(error(context, pos, ErrorKind::TypeError))
+            }
+            _ => return Err(error(context, pos, ErrorKind::MathError)),
+        };
+        out_of_range(integer, context, pos)
+    }
+
+    // indices and the bounds of ranges have to be integers, other numbers are the wrong type
+    pub fn expect_integer(&self, context: &str, pos: usize) -> Result<(), instruction::Error> {
+        let integer = match self {
+            Value::Integer(_) => true,
+            Value::Rational(q) => q.den == 1,
+            Value::Real(x) => x.fract().is_zero(),
+            Value::Precise(x) => x.is_integer(),
+            Value::Float(x) => x.fract() == 0.0,
+            Value::Complex(_) | Value::List(_) | Value::String(_) | Value::Function(_) => false,
+        };
+        match integer {
+            true => Ok(()),
+            false => Err(error(context, pos, ErrorKind::TypeError)),
+        }
+    }
+
+    // the result of a bitwise operation as the same kind of number as the operands,
+    // checked is None if the result overflows an i128
+    fn integer_result(
+        &self,
+        checked: Option<i128>,
+        wrapping: i128,
+        context: &str,
+        pos: usize,
+    ) -> Result<Value, instruction::Error> {
+        match self {
+            Value::Integer(_) => Ok(Value::Integer(integer::result(
+                checked, wrapping, context, pos,
+            )?)),
+            Value::Rational(_) => out_of_range(
+                checked.and_then(|x| Rational::new(x, 1)).map(Value::Rational),
+                context,
+                pos,
+            ),
+            Value::Precise(_) => out_of_range(
+                checked.map(|x| Value::Precise(BigDecimal::from(x))),
+                context,
+                pos,
+            ),
+            Value::Float(_) => out_of_range(checked.map(|x| Value::Float(x as f64)), context, pos),
+            _ => out_of_range(
+                checked.and_then(Decimal::from_i128).map(Value::Real),
+                context,
+                pos,
+            ),
+        }
+    }
+
+    // the operands of a bitwise operation, and the kind of number of its result
+    fn integer_operands(
+        &self,
+        rhs: &Value,
+        context: &str,
+        pos: usize,
+    ) -> Result<(i128, i128, Value), instruction::Error> {
+        let (lhs, rhs) = self.promote(rhs, context, pos)?;
+        let (a, b) = (lhs.to_integer(context, pos)?, rhs.to_integer(context, pos)?);
+        match lhs {
+            Value::Integer(_) => Ok((a, b, rhs)),
+            _ => Ok((a, b, lhs)),
+        }
+    }
+
+    pub fn bit_and(
+        &self,
+        rhs: &Value,
+        context: &str,
+        pos: usize,
+    ) -> Result<Value, instruction::Error> {
+        let (a, b, kind) = self.integer_operands(rhs, context, pos)?;
+        kind.integer_result(Some(a & b), a & b, context, pos)
+    }
+
+    pub fn bit_or(
+        &self,
+        rhs: &Value,
+        context: &str,
+        pos: usize,
+    ) -> Result<Value, instruction::Error> {
+        let (a, b, kind) = self.integer_operands(rhs, context, pos)?;
+        kind.integer_result(Some(a | b), a | b, context, pos)
+    }
+
+    pub fn bit_xor(
+        &self,
+        rhs: &Value,
+        context: &str,
+        pos: usize,
+    ) -> Result<Value, instruction::Error> {
+        let (a, b, kind) = self.integer_operands(rhs, context, pos)?;
+        kind.integer_result(Some(a ^ b), a ^ b, context, pos)
+    }
+
+    // the list of the integers from self up to, but not including, end
+    pub fn range(
+        &self,
+        end: &Value,
+        context: &str,
+        pos: usize,
+    ) -> Result<Value, instruction::Error> {
+        self.expect_integer(context, pos)?;
+        end.expect_integer(context, pos)?;
+        let (start, end, kind) = self.integer_operands(end, context, pos)?;
+        if end.saturating_sub(start) > LOOP_LIMIT as i128 {
+            return Err(instruction::Error::new(
+                String::new(),
+                0,
+                ErrorKind::IterationLimitReached,
+            ));
+        }
+
+        let mut elements = Vec::new();
+        for x in start..end {
+            elements.push(kind.integer_result(Some(x), x, context, pos)?);
+        }
+        Ok(Value::List(elements))
+    }
+
+    // the complement within the integer type, so that '~0' is 255 for u8
+    pub fn bit_not(&self, context: &str, pos: usize) -> Result<Value, instruction::Error> {
+        let x = !self.to_integer(context, pos)?;
+        match self {
+            Value::Integer(_) => self.integer_result(Some(integer::wrap(x)), x, context, pos),
+            _ => self.integer_result(Some(x), x, context, pos),
+        }
+    }
+
+    // shifting by a negative number of bits is out of range, like a negative number in
+    // an unsigned integer mode
+    pub fn shift_left(
+        &self,
+        rhs: &Value,
+        context: &str,
+        pos: usize,
+    ) -> Result<Value, instruction::Error> {
+        let (x, shift, kind) = self.integer_operands(rhs, context, pos)?;
+        let (checked, wrapping) = match shift {
+            _ if shift < 0 => return Err(error(context, pos, ErrorKind::OutOfRange)),
+            _ if x == 0 => (Some(0), 0),
+            0..=127 => {
+                let shifted = x << shift;
+                ((shifted >> shift == x).then_some(shifted), shifted)
+            }
+            _ => (None, 0),
+        };
+        kind.integer_result(checked, wrapping, context, pos)
+    }
+
+    // an arithmetic shift, which keeps the sign
+    pub fn shift_right(
+        &self,
+        rhs: &Value,
+        context: &str,
+        pos: usize,
+    ) -> Result<Value, instruction::Error> {
+        let (x, shift, kind) = self.integer_operands(rhs, context, pos)?;
+        if shift < 0 {
+            return Err(error(context, pos, ErrorKind::OutOfRange));
+        }
+        let result = x >> shift.min(127);
+        kind.integer_result(Some(result), result, context, pos)
+    }
+}
+
+fn error(context: &str, pos: usize, kind: ErrorKind) -> instruction::Error {
+    instruction::Error::new(context.to_string(), pos, kind)
+}
+
+fn out_of_range<T>(value: Option<T>, context: &str, pos: usize) -> Result<T, instruction::Error> {
+    value.ok_or_else(|| error(context, pos, ErrorKind::OutOfRange))
+}
+
+fn precise_result(
+    value: Option<BigDecimal>,
+    context: &str,
+    pos: usize,
+) -> Result<Value, instruction::Error> {
+    match value {
+        Some(value) => Ok(Value::Precise(value)),
+        None => Err(error(context, pos, ErrorKind::MathError)),
+    }
+}
+
+impl Rational {
+    // the largest mantissa of a Decimal
+    const LIMIT: i128 = (1 << 96) - 1;
+
+    // None if the denominator is zero, or the reduced parts don't fit into a Decimal
+    fn new(num: i128, den: i128) -> Option<Rational> {
+        if den == 0 {
+            return None;
+        }
+
+        let divisor = gcd(num, den);
+        let (mut num, mut den) = (num / divisor, den / divisor);
+        if den < 0 {
+            num = -num;
+            den = -den;
+        }
+
+        if num.abs() > Rational::LIMIT || den > Rational::LIMIT {
+            None
+        } else {
+            Some(Rational { num, den })
+        }
+    }
+
+    // every Decimal is a fraction with a power of ten as the denominator
+    pub fn from_decimal(value: Decimal) -> Rational {
+        Rational::new(value.mantissa(), 10i128.pow(value.scale()))
+            .expect("The parts of a Decimal always fit into a rational")
+    }
+
+    pub fn to_decimal(self) -> Decimal {
+        Decimal::from_i128(self.num).unwrap() / Decimal::from_i128(self.den).unwrap()
+    }
+
+    // None if the exponent is not an integer
+    fn to_i64(self) -> Option<i64> {
+        if self.den == 1 {
+            self.num.to_i64()
+        } else {
+            None
+        }
+    }
+
+    fn neg(&self) -> Rational {
+        Rational {
+            num: -self.num,
+            den: self.den,
+        }
+    }
+
+    fn checked_add(&self, rhs: &Rational) -> Option<Rational> {
+        Rational::new(
+            self.num
+                .checked_mul(rhs.den)?
+                .checked_add(rhs.num.checked_mul(self.den)?)?,
+            self.den.checked_mul(rhs.den)?,
+        )
+    }
+
+    fn checked_mul(&self, rhs: &Rational) -> Option<Rational> {
+        Rational::new(
+            self.num.checked_mul(rhs.num)?,
+            self.den.checked_mul(rhs.den)?,
+        )
+    }
+
+    fn checked_div(&self, rhs: &Rational) -> Option<Rational> {
+        Rational::new(
+            self.num.checked_mul(rhs.den)?,
+            self.den.checked_mul(rhs.num)?,
+        )
+    }
+
+    fn checked_rem(&self, rhs: &Rational) -> Option<Rational> {
+        let quotient = self.checked_div(rhs)?;
+        let whole = Rational::new(quotient.num / quotient.den, 1)?;
+        self.checked_add(&whole.checked_mul(rhs)?.neg())
+    }
+
+    fn checked_powi(&self, exponent: i64) -> Option<Rational> {
+        // powers of 0, 1 and -1 don't grow, however large the exponent
+        if self.num == 0 && exponent > 0 {
+            return Some(*self);
+        }
+        if self.den == 1 && self.num.abs() == 1 {
+            let num = if exponent % 2 == 0 { 1 } else { self.num };
+            return Rational::new(num, 1);
+        }
+        let exponent_abs = u32::try_from(exponent.unsigned_abs()).ok()?;
+        let power = Rational::new(
+            self.num.checked_pow(exponent_abs)?,
+            self.den.checked_pow(exponent_abs)?,
+        )?;
+        if exponent < 0 {
+            Rational::new(power.den, power.num)
+        } else {
+            Some(power)
+        }
+    }
+
+    fn compare(&self, rhs: &Rational) -> Option<Ordering> {
+        Some(
+            self.num
+                .checked_mul(rhs.den)?
+                .cmp(&rhs.num.checked_mul(self.den)?),
+        )
+    }
+}
+
+fn gcd(a: i128, b: i128) -> i128 {
+    let (mut a, mut b) = (a.abs(), b.abs());
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a.max(1)
+}
+
+fn complex_enabled() -> bool {
+    session::settings().complex_numbers
+}
+
+impl Neg for Value {
+    type Output = Value;
+
+    fn neg(self) -> Value {
+        match self {
+            Value::Real(x) => Value::Real(-x),
+            Value::Rational(q) => Value::Rational(q.neg()),
+            Value::Complex(z) => Value::complex(-z.re, -z.im),
+            Value::Precise(x) => Value::Precise(-x),
+            Value::Float(x) => Value::Float(-x),
+            Value::Integer(x) => Value::Integer(-x),
+            Value::List(_) | Value::String(_) | Value::Function(_) => {
+                unreachable!("Only numbers are negated")
+            }
+        }
+    }
+}
+
+// rational and precise numbers are compared exactly, floats as floats, so NaN is
+// not equal to itself, lists element by element, strings by their characters,
+// functions by their identity, and everything else by its Decimal parts
+impl PartialEq for Value {
+    fn eq(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::List(lhs), Value::List(rhs)) => lhs == rhs,
+            (Value::String(lhs), Value::String(rhs)) => lhs == rhs,
+            (Value::Function(lhs), Value::Function(rhs)) => lhs == rhs,
+            (Value::List(_) | Value::String(_) | Value::Function(_), _)
+            | (_, Value::List(_) | Value::String(_) | Value::Function(_)) => false,
+            (Value::Rational(lhs), Value::Rational(rhs)) => lhs == rhs,
+            (Value::Precise(_) | Value::Float(_), Value::Complex(_))
+            | (Value::Complex(_), Value::Precise(_) | Value::Float(_)) => false,
+            (Value::Float(_), _) | (_, Value::Float(_)) => {
+                self.float("", 0).ok() == other.float("", 0).ok()
+            }
+            (Value::Precise(_), _) | (_, Value::Precise(_)) => {
+                let precision = precise::precision();
+                self.to_precise(precision) == other.to_precise(precision)
+            }
+            _ => self.re() == other.re() && self.im() == other.im(),
+        }
+    }
+}
+
+// complex numbers are written like '3 + 4i', '-2i' or '1 - i',
+// rational numbers like '1/3' or, if larger than one, as mixed numbers like '-1 2/3',
+// lists like '[1, 2, 3]', and strings as they are, unless they are in a list
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Real(x) => write!(f, "{x}"),
+            Value::Float(x) => write!(f, "{x}"),
+            Value::Integer(x) => write!(f, "{}", integer::format(*x)),
+            Value::List(elements) => {
+                let elements: Vec<String> = elements
+                    .iter()
+                    .map(|element| match element {
+                        Value::String(string) => format!("{string:?}"),
+                        _ => element.to_string(),
+                    })
+                    .collect();
+                write!(f, "[{}]", elements.join(", "))
+            }
+            Value::String(string) => write!(f, "{string}"),
+            Value::Function(function) => write!(f, "{function}"),
+            Value::Precise(x) => write!(f, "{}", precise::format(x, precise::precision())),
+            Value::Rational(q) => {
+                let whole = q.num / q.den;
+                let numerator = q.num % q.den;
+                if numerator == 0 {
+                    write!(f, "{whole}")
+                } else if whole == 0 {
+                    write!(f, "{numerator}/{}", q.den)
+                } else {
+                    write!(f, "{whole} {}/{}", numerator.abs(), q.den)
+                }
+            }
+            Value::Complex(z) => {
+                let coefficient = if z.im.abs() == Decimal::ONE {
+                    String::new()
+                } else {
+                    z.im.abs().to_string()
+                };
+                let sign = if z.im.is_sign_negative() { "-" } else { "" };
+
+                if z.re.is_zero() {
+                    write!(f, "{sign}{coefficient}i")
+                } else if z.im.is_sign_negative() {
+                    write!(f, "{} - {coefficient}i", z.re)
+                } else {
+                    write!(f, "{} + {coefficient}i", z.re)
+                }
+            }
+        }
+    }
+}
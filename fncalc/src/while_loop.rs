@@ -1,6 +1,6 @@
 use crate::expression::Expression;
 use crate::instruction::{self, Instruction, ReturnValue};
-use crate::{LOOP_LIMIT, ZERO};
+use crate::LOOP_LIMIT;
 
 #[derive(Debug, Clone)]
 pub struct WhileLoop {
@@ -33,7 +33,7 @@ impl WhileLoop {
 
         let mut loop_counter: usize = 0;
 
-        'main_loop: while condition_result != ZERO {
+        'main_loop: while !condition_result.is_zero() {
             if loop_counter >= LOOP_LIMIT {
                 return Err(instruction::Error::new(
                     String::new(),
@@ -51,6 +51,9 @@ impl WhileLoop {
                         ReturnValue::Return(value) => {
                             return Ok(ReturnValue::Return(value));
                         }
+                        ReturnValue::TailCall(tail_call) => {
+                            return Ok(ReturnValue::TailCall(tail_call));
+                        }
                         ReturnValue::Break => {
                             break 'main_loop;
                         }